@@ -0,0 +1,42 @@
+//! Finds every `src/days/dayNN.rs` and generates the module declarations and
+//! registry that `src/days/mod.rs` includes, so adding a day only takes
+//! adding its file.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let days_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("src/days");
+    println!("cargo:rerun-if-changed={}", days_dir.display());
+
+    let mut days: BTreeMap<u8, String> = BTreeMap::new();
+    for entry in fs::read_dir(&days_dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "rs") {
+            continue;
+        }
+        let stem = path.file_stem().unwrap().to_str().unwrap().to_string();
+        let Some(number) = stem.strip_prefix("day").and_then(|n| n.parse::<u8>().ok()) else {
+            continue;
+        };
+        if let Some(other) = days.insert(number, stem.clone()) {
+            panic!("Day {} is defined by both {}.rs and {}.rs", number, other, stem);
+        }
+    }
+
+    let mut out = String::new();
+    for module in days.values() {
+        let path = days_dir.join(format!("{}.rs", module));
+        out += &format!("#[path = {:?}]\npub mod {};\n", path.display().to_string(), module);
+    }
+    out += "\nfn registered() -> Vec<(u8, Arc<dyn Day>)> {\n    vec![\n";
+    for (number, module) in &days {
+        out += &format!("        ({}, {}::register()),\n", number, module);
+    }
+    out += "    ]\n}\n";
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("days.rs");
+    fs::write(dest, out).unwrap();
+}
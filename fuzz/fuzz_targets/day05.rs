@@ -0,0 +1,13 @@
+#![no_main]
+
+use advent2022::days::day05;
+use advent2022::input::read_lines_from;
+use advent2022::interface::Day;
+use libfuzzer_sys::fuzz_target;
+
+// Parsing any input must return an error rather than panic.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(lines) = read_lines_from(data) {
+        let _ = day05::register().parse(&lines);
+    }
+});
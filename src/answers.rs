@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context};
+
+use crate::interface::*;
+
+pub const DEFAULT_PATH: &str = "answers.toml";
+
+/// Known-good answers, keyed by day and part.
+///
+/// On disk this is a TOML file with one table per day:
+///
+/// ```toml
+/// [day01]
+/// part1 = "24000"
+/// part2 = "45000"
+/// ```
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Answers {
+    entries: BTreeMap<(u8, u8), String>,
+}
+
+fn parse_key(key: &str, prefix: &str) -> Result<u8> {
+    key.strip_prefix(prefix)
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| anyhow!("Invalid key '{}', expected {}N", key, prefix))
+}
+
+impl Answers {
+    /// Loads answers from `path`, treating a missing file as empty.
+    pub fn load(path: &Path) -> Result<Answers> {
+        match fs::read_to_string(path) {
+            Ok(s) => Answers::parse(&s).with_context(|| format!("Reading {}", path.display())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Answers::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Answers> {
+        let table: BTreeMap<String, BTreeMap<String, toml::Value>> = toml::from_str(s)?;
+        let mut answers = Answers::default();
+        for (day_key, parts) in table {
+            let day = parse_key(&day_key, "day")?;
+            for (part_key, value) in parts {
+                let part = parse_key(&part_key, "part")?;
+                let answer = match value {
+                    toml::Value::String(s) => s,
+                    toml::Value::Integer(i) => i.to_string(),
+                    _ => bail!("Answer for {}.{} must be a string or integer", day_key, part_key),
+                };
+                answers.entries.insert((day, part), answer);
+            }
+        }
+        Ok(answers)
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.entries.get(&(day, part)).map(|s| s.as_str())
+    }
+
+    pub fn insert(&mut self, day: u8, part: u8, answer: &Answer) {
+        self.entries.insert((day, part), answer.to_string());
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        let mut table: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        for ((day, part), answer) in &self.entries {
+            table
+                .entry(format!("day{:02}", day))
+                .or_default()
+                .insert(format!("part{}", part), answer.clone());
+        }
+        Ok(toml::to_string(&table)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_toml()?).with_context(|| format!("Writing {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let answers = Answers::parse(
+            r#"
+[day01]
+part1 = "24000"
+part2 = 45000
+
+[day05]
+part1 = "CMZ"
+"#,
+        )
+        .unwrap();
+        assert_eq!(answers.get(1, 1), Some("24000"));
+        assert_eq!(answers.get(1, 2), Some("45000"));
+        assert_eq!(answers.get(5, 1), Some("CMZ"));
+        assert_eq!(answers.get(5, 2), None);
+        assert!(Answers::parse("[dayone]\npart1 = \"1\"").is_err());
+        assert!(Answers::parse("[day01]\npart1 = true").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut answers = Answers::default();
+        answers.insert(1, 1, &Answer::from(24000));
+        answers.insert(10, 2, &Answer::from(vec!["# #".to_string(), " # ".to_string()]));
+        let reparsed = Answers::parse(&answers.to_toml().unwrap()).unwrap();
+        assert_eq!(reparsed, answers);
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::cancel::with_budget;
+use crate::cli::Options;
+use crate::input::InputSet;
+use crate::interface::*;
+use crate::runner::{selected_parts, solve};
+
+/// Summary of repeated timings of the same piece of work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(mut samples: Vec<Duration>) -> Option<Stats> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort();
+        Some(Stats {
+            min: samples[0],
+            median: samples[samples.len() / 2],
+            max: samples[samples.len() - 1],
+        })
+    }
+}
+
+pub fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+/// Times `f` on the budget the runner would give it, so that a run that
+/// hangs or panics fails instead of stopping the whole benchmark.
+fn time_within<T: Send + 'static>(
+    budget: Option<Duration>,
+    f: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<(T, Duration)> {
+    with_budget(budget, move || {
+        let (result, elapsed) = time(f);
+        Ok((result?, elapsed))
+    })
+}
+
+fn fmt_duration(d: Duration) -> String {
+    format!("{:.1?}", d)
+}
+
+fn print_row(day: u8, phase: &str, stats: &Stats) {
+    println!(
+        "{:>3}  {:<7}  {:>10}  {:>10}  {:>10}",
+        day,
+        phase,
+        fmt_duration(stats.min),
+        fmt_duration(stats.median),
+        fmt_duration(stats.max)
+    );
+}
+
+/// Times reading the input, parsing it and solving each selected part
+/// `opts.iterations` times, printing a table of the results. Parsing and
+/// each part run on `--timeout` or the day's own budget, as in a normal run.
+/// Returns the number of failures.
+pub fn run(days: &[Arc<dyn Day>], inputs: &InputSet, opts: &Options) -> usize {
+    println!("Day  Phase           Min      Median         Max");
+    let mut failures = 0;
+    for day in days {
+        let mut input_samples = vec![];
+        let mut input = vec![];
+        for _ in 0..opts.iterations {
+            let (result, elapsed) = time(|| inputs.load(day.number()));
+            match result {
+                Ok(lines) => input = lines,
+                Err(e) => {
+                    eprintln!("Day {:02} input failed: {:#}", day.number(), e);
+                    break;
+                }
+            }
+            input_samples.push(elapsed);
+        }
+        let Some(stats) = Stats::from_samples(input_samples) else {
+            failures += 1;
+            continue;
+        };
+        print_row(day.number(), "input", &stats);
+
+        let budget = opts.timeout.or_else(|| day.budget());
+        let input = Arc::new(input);
+        let mut parse_samples = vec![];
+        let mut parsed = None;
+        for _ in 0..opts.iterations {
+            let (d, input) = (Arc::clone(day), Arc::clone(&input));
+            match time_within(budget, move || d.parse(&input)) {
+                Ok((p, elapsed)) => {
+                    parsed = Some(p);
+                    parse_samples.push(elapsed);
+                }
+                Err(e) => {
+                    eprintln!("Day {:02} parse failed: {:#}", day.number(), e);
+                    break;
+                }
+            }
+        }
+        let (Some(stats), Some(parsed)) = (Stats::from_samples(parse_samples), parsed) else {
+            failures += 1;
+            continue;
+        };
+        print_row(day.number(), "parse", &stats);
+
+        for part in selected_parts(opts) {
+            let mut samples = vec![];
+            for _ in 0..opts.iterations {
+                let (d, parsed) = (Arc::clone(day), Arc::clone(&parsed));
+                match time_within(budget, move || solve(d.as_ref(), part, &*parsed)) {
+                    Ok((_, elapsed)) => samples.push(elapsed),
+                    Err(e) => {
+                        eprintln!("Day {:02} part {} failed: {:#}", day.number(), part, e);
+                        failures += 1;
+                        break;
+                    }
+                }
+            }
+            if let Some(stats) = Stats::from_samples(samples) {
+                print_row(day.number(), &format!("part {}", part), &stats);
+            }
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let ms = Duration::from_millis;
+        let stats = Stats::from_samples(vec![ms(5), ms(1), ms(3), ms(9), ms(2)]).unwrap();
+        assert_eq!(stats.min, ms(1));
+        assert_eq!(stats.median, ms(3));
+        assert_eq!(stats.max, ms(9));
+        assert_eq!(Stats::from_samples(vec![]), None);
+    }
+}
@@ -0,0 +1,130 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use crate::interface::*;
+
+/// Shared flag the runner sets when a part has used up its time budget.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancelToken>> = const { RefCell::new(None) };
+}
+
+/// The error reported for a part that ran past its time budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout(pub Duration);
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TIMEOUT after {:.1?}", self.0)
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// Fails once the runner has given up on the current part. Long-running
+/// loops should call this every so often so that their thread stops
+/// promptly instead of burning CPU after a timeout.
+pub fn check_cancelled() -> Result<()> {
+    CURRENT.with(|current| match &*current.borrow() {
+        Some(token) if token.is_cancelled() => Err(anyhow!("Cancelled")),
+        _ => Ok(()),
+    })
+}
+
+/// Runs `f`, turning a panic into an error so that one broken part can't
+/// take the whole run down with it.
+fn catching<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned());
+        match message {
+            Some(message) => Err(anyhow!("Panicked: {}", message)),
+            None => Err(anyhow!("Panicked")),
+        }
+    })
+}
+
+/// Runs `f`, giving up after `budget` if there is one. With a budget, `f`
+/// runs on its own thread; on timeout that thread is told to cancel and is
+/// left behind, and the result is a [`Timeout`] error. Either way a panic
+/// in `f` comes back as an error.
+pub fn with_budget<T, F>(budget: Option<Duration>, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let Some(budget) = budget else {
+        return catching(f);
+    };
+    let token = CancelToken::default();
+    let thread_token = token.clone();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        CURRENT.with(|current| *current.borrow_mut() = Some(thread_token));
+        // The receiver is gone if we timed out, and nobody wants the result.
+        let _ = tx.send(catching(f));
+    });
+    match rx.recv_timeout(budget) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            token.cancel();
+            Err(Timeout(budget).into())
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(anyhow!("Panicked")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_budget() {
+        assert_eq!(with_budget(None, || Ok(3)).unwrap(), 3);
+        let budget = Some(Duration::from_secs(5));
+        assert_eq!(with_budget(budget, || Ok(3)).unwrap(), 3);
+        let err = with_budget(budget, || -> Result<()> { panic!("boom") }).unwrap_err();
+        assert_eq!(err.to_string(), "Panicked: boom");
+        let err = with_budget(None, || -> Result<()> { panic!("{}", 3) }).unwrap_err();
+        assert_eq!(err.to_string(), "Panicked: 3");
+    }
+
+    #[test]
+    fn test_timeout_cancels() {
+        let (tx, rx) = mpsc::channel();
+        let budget = Duration::from_millis(20);
+        let err = with_budget(Some(budget), move || loop {
+            if check_cancelled().is_err() {
+                tx.send(()).unwrap();
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(1));
+        })
+        .unwrap_err();
+        assert_eq!(err.downcast_ref::<Timeout>(), Some(&Timeout(budget)));
+        // the abandoned thread notices and stops
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(check_cancelled().is_ok());
+    }
+}
@@ -0,0 +1,404 @@
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+
+use crate::answers;
+use crate::frames;
+use crate::interface::*;
+
+pub const USAGE: &str = "Usage: advent2022 [OPTIONS]
+       advent2022 new-day <DAY>
+
+Commands:
+  new-day <DAY>       Create src/days/dayNN.rs and an example fixture for its tests
+
+Options:
+  -y, --year <YEAR>   Puzzle year to run (only 2022 is available)
+  -d, --day <DAYS>    Run only the given days, e.g. `11`, `3-7` or `1,5-6`
+  -p, --part <PART>   Run only part 1 or part 2
+      --include-slow  Also run days marked as slow, which are skipped by default
+      --fast          Skip days marked as slow. This is now the default, and the
+                      flag is only kept for existing scripts
+  -t, --timeout <SECS>
+                      Give up on parsing or a part after SECS seconds, overriding
+                      the day's own budget
+      --parallel      Run days concurrently, one thread per CPU
+  -j, --jobs <N>      Run days concurrently on N threads
+      --watch         Keep running, and re-run a day whenever its input or fixture
+                      changes
+      --tui           Show the days on a live calendar, where any day can be
+                      selected and run again
+      --frames <TARGET>
+                      Show the selected day's simulation step by step: `ansi` to
+                      animate it in the terminal, `text:PATH` to write every frame
+                      to PATH, or `gif:PATH` to write an animated GIF
+      --frame-every <N>
+                      Keep only every Nth frame [default: 1]
+      --bench         Time each part instead of printing answers
+  -n, --iterations <N>
+                      Number of runs per part in --bench mode [default: 10]
+      --verify        Check answers against the answer registry
+      --record        Like --verify, but add answers missing from the registry
+      --fetch         Download inputs that are missing from the input directory,
+                      using the session cookie in $ADVENT_SESSION
+      --submit        Submit the answer for the selected day and part, and record
+                      it in the answer registry if it is right
+      --base-url <URL>
+                      Puzzle server to fetch from and submit to
+                      [default: $ADVENT_BASE_URL or https://adventofcode.com]
+      --answers <PATH>
+                      Answer registry to use [default: answers.toml]. Not
+                      allowed with --input-set
+      --report <PATH> Write a JSON report of every part to PATH, as JSON lines
+                      if PATH ends in .jsonl
+  -i, --input <PATH>  Read the input for the selected day from PATH, or stdin if PATH is `-`
+      --input-dir <DIR>
+                      Directory holding inputNN.txt files [default: $ADVENT_INPUT_DIR or .]
+  -s, --input-set <NAMES>
+                      Run against each named input set, e.g. `alice,bob`, read from
+                      subdirectories of the input directory. Each set keeps its own
+                      answers.toml
+  -h, --help          Print this message";
+
+/// What to do instead of running days, if anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    NewDay(u8),
+}
+
+/// Which days and parts the runner should execute, and how.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub command: Command,
+    pub year: Option<u16>,
+    /// Selected day ranges; empty means every registered day.
+    pub days: Vec<RangeInclusive<u8>>,
+    pub part: Option<u8>,
+    pub include_slow: bool,
+    pub timeout: Option<Duration>,
+    pub parallel: bool,
+    pub jobs: Option<usize>,
+    pub watch: bool,
+    pub tui: bool,
+    pub frames: Option<frames::Target>,
+    pub frame_every: usize,
+    pub bench: bool,
+    pub iterations: usize,
+    pub verify: bool,
+    pub record: bool,
+    pub fetch: bool,
+    pub submit: bool,
+    pub base_url: Option<String>,
+    pub answers: PathBuf,
+    pub report: Option<PathBuf>,
+    pub input: Option<PathBuf>,
+    pub input_dir: Option<PathBuf>,
+    pub input_sets: Vec<String>,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            command: Command::Run,
+            year: None,
+            days: vec![],
+            part: None,
+            include_slow: false,
+            timeout: None,
+            parallel: false,
+            jobs: None,
+            watch: false,
+            tui: false,
+            frames: None,
+            frame_every: 1,
+            bench: false,
+            iterations: 10,
+            verify: false,
+            record: false,
+            fetch: false,
+            submit: false,
+            base_url: None,
+            answers: PathBuf::from(answers::DEFAULT_PATH),
+            report: None,
+            input: None,
+            input_dir: None,
+            input_sets: vec![],
+            help: false,
+        }
+    }
+}
+
+impl Options {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options> {
+        let mut opts = Options::default();
+        let mut answers_given = false;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // Accept both `--day 3` and `--day=3`.
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, val)) if flag.starts_with("--") => (flag.to_string(), Some(val.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| anyhow!("Missing value for {}", flag))
+            };
+            match flag.as_str() {
+                "-y" | "--year" => opts.year = Some(value()?.parse()?),
+                "-d" | "--day" => {
+                    for spec in value()?.split(',') {
+                        opts.days.push(parse_days(spec)?);
+                    }
+                }
+                "-p" | "--part" => {
+                    let part: u8 = value()?.parse()?;
+                    if part != 1 && part != 2 {
+                        bail!("Invalid part {}, expected 1 or 2", part);
+                    }
+                    opts.part = Some(part);
+                }
+                "--include-slow" => opts.include_slow = true,
+                // deprecated: skipping slow days became the default
+                "--fast" => opts.include_slow = false,
+                "-t" | "--timeout" => {
+                    let secs: f64 = value()?.parse()?;
+                    if !(secs > 0.0 && secs.is_finite()) {
+                        bail!("Invalid timeout {}", secs);
+                    }
+                    opts.timeout = Some(Duration::from_secs_f64(secs));
+                }
+                "--parallel" => opts.parallel = true,
+                "-j" | "--jobs" => {
+                    let jobs = value()?.parse()?;
+                    if jobs == 0 {
+                        bail!("--jobs must be at least 1");
+                    }
+                    opts.jobs = Some(jobs);
+                }
+                "--watch" => opts.watch = true,
+                "--tui" => opts.tui = true,
+                "--frames" => opts.frames = Some(value()?.parse()?),
+                "--frame-every" => {
+                    opts.frame_every = value()?.parse()?;
+                    if opts.frame_every == 0 {
+                        bail!("--frame-every must be at least 1");
+                    }
+                }
+                "--bench" => opts.bench = true,
+                "-n" | "--iterations" => {
+                    opts.iterations = value()?.parse()?;
+                    if opts.iterations == 0 {
+                        bail!("--iterations must be at least 1");
+                    }
+                }
+                "--verify" => opts.verify = true,
+                "--record" => opts.record = true,
+                "--fetch" => opts.fetch = true,
+                "--submit" => opts.submit = true,
+                "--base-url" => opts.base_url = Some(value()?),
+                "--answers" => {
+                    opts.answers = PathBuf::from(value()?);
+                    answers_given = true;
+                }
+                "--report" => opts.report = Some(PathBuf::from(value()?)),
+                "-i" | "--input" => opts.input = Some(PathBuf::from(value()?)),
+                "--input-dir" => opts.input_dir = Some(PathBuf::from(value()?)),
+                "-s" | "--input-set" => {
+                    for name in value()?.split(',') {
+                        opts.input_sets.push(name.trim().to_string());
+                    }
+                }
+                "-h" | "--help" => opts.help = true,
+                "new-day" if opts.command == Command::Run => {
+                    let day: u8 = value()?.parse()?;
+                    if !(1..=25).contains(&day) {
+                        bail!("Invalid day {}, days run from 1 to 25", day);
+                    }
+                    opts.command = Command::NewDay(day);
+                }
+                _ => bail!("Unknown argument: '{}'", arg),
+            }
+        }
+        if opts.input.is_some() && !opts.input_sets.is_empty() {
+            bail!("--input can't be combined with --input-set");
+        }
+        if answers_given && !opts.input_sets.is_empty() {
+            bail!("--answers can't be combined with --input-set, each set keeps its own answers.toml");
+        }
+        if opts.watch && (opts.bench || opts.verify || opts.record) {
+            bail!("--watch can't be combined with --bench, --verify or --record");
+        }
+        if opts.watch && opts.input.as_deref() == Some(Path::new("-")) {
+            bail!("--watch needs input files, not stdin");
+        }
+        if opts.tui && (opts.watch || opts.bench || opts.verify || opts.record || opts.submit) {
+            bail!("--tui can't be combined with --watch, --bench, --verify, --record or --submit");
+        }
+        if opts.tui && opts.input.as_deref() == Some(Path::new("-")) {
+            bail!("--tui reads keys from the terminal, so its input can't come from stdin");
+        }
+        if opts.frames.is_some() {
+            if !opts.selects_one_day() {
+                bail!("--frames needs exactly one --day");
+            }
+            if opts.watch || opts.tui || opts.bench {
+                bail!("--frames can't be combined with --watch, --tui or --bench");
+            }
+        }
+        if opts.fetch && !opts.input_sets.is_empty() {
+            bail!("--fetch can't be combined with --input-set, the inputs belong to one account");
+        }
+        if opts.submit {
+            if !opts.selects_one_day() || opts.part.is_none() {
+                bail!("--submit needs exactly one --day and a --part");
+            }
+            if opts.watch || opts.bench || opts.verify || opts.record {
+                bail!("--submit can't be combined with --watch, --bench, --verify or --record");
+            }
+        }
+        // only a plain run fills in the report
+        if opts.report.is_some()
+            && (opts.bench || opts.verify || opts.record || opts.submit || opts.watch || opts.tui)
+        {
+            bail!("--report can't be combined with --bench, --verify, --record, --submit, --watch or --tui");
+        }
+        Ok(opts)
+    }
+
+    fn selects_one_day(&self) -> bool {
+        self.days.len() == 1 && self.days[0].start() == self.days[0].end()
+    }
+
+    pub fn selects_day(&self, day: &dyn Day) -> bool {
+        if day.slow() && !self.include_slow {
+            return false;
+        }
+        self.days.is_empty() || self.days.iter().any(|r| r.contains(&day.number()))
+    }
+
+    pub fn selects_part(&self, part: u8) -> bool {
+        self.part.is_none_or(|p| p == part)
+    }
+
+    /// How many worker threads to run days on, or `None` to run them one
+    /// after another on the current thread.
+    pub fn threads(&self) -> Option<usize> {
+        match (self.jobs, self.parallel) {
+            (Some(jobs), _) => Some(jobs),
+            (None, true) => Some(thread::available_parallelism().map_or(1, |n| n.get())),
+            (None, false) => None,
+        }
+    }
+}
+
+fn parse_days(spec: &str) -> Result<RangeInclusive<u8>> {
+    let range: RangeInclusive<u8> = match spec.split_once('-') {
+        Some((first, last)) => first.trim().parse()?..=last.trim().parse()?,
+        None => {
+            let day = spec.trim().parse()?;
+            day..=day
+        }
+    };
+    if range.is_empty() || *range.start() < 1 || *range.end() > 25 {
+        bail!("Invalid day selection '{}', days run from 1 to 25", spec);
+    }
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        Options::parse(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(&[]).unwrap(), Options::default());
+        let opts = parse(&["--day", "11", "--part", "2"]).unwrap();
+        assert_eq!(opts.days, vec![11..=11]);
+        assert_eq!(opts.part, Some(2));
+        let opts = parse(&["--year", "2022", "--day=1,5-7", "--include-slow"]).unwrap();
+        assert_eq!(opts.year, Some(2022));
+        assert_eq!(opts.days, vec![1..=1, 5..=7]);
+        assert!(opts.include_slow);
+        assert!(!parse(&["--include-slow", "--fast"]).unwrap().include_slow);
+        let opts = parse(&["--timeout", "2.5"]).unwrap();
+        assert_eq!(opts.timeout, Some(Duration::from_millis(2500)));
+        assert_eq!(parse(&["-d", "3-4", "-p", "1"]).unwrap().days, vec![3..=4]);
+        let opts = parse(&["--bench", "-n", "5"]).unwrap();
+        assert!(opts.bench);
+        assert_eq!(opts.iterations, 5);
+        let opts = parse(&["--record", "--answers", "mine.toml"]).unwrap();
+        assert!(opts.record);
+        assert_eq!(opts.answers, PathBuf::from("mine.toml"));
+        let opts = parse(&["--report", "run.jsonl"]).unwrap();
+        assert_eq!(opts.report, Some(PathBuf::from("run.jsonl")));
+        let opts = parse(&["--input-dir", "inputs", "-s", "alice,bob", "-s", "carol"]).unwrap();
+        assert_eq!(opts.input_dir, Some(PathBuf::from("inputs")));
+        assert_eq!(opts.input_sets, vec!["alice", "bob", "carol"]);
+        assert_eq!(parse(&["new-day", "17"]).unwrap().command, Command::NewDay(17));
+        assert!(parse(&["--watch", "-d", "5"]).unwrap().watch);
+        assert!(parse(&["--tui", "-j", "4"]).unwrap().tui);
+        let opts = parse(&["-d", "14", "--frames", "gif:sand.gif", "--frame-every", "10"]).unwrap();
+        assert_eq!(opts.frames, Some(frames::Target::Gif(PathBuf::from("sand.gif"))));
+        assert_eq!(opts.frame_every, 10);
+        let opts = parse(&["--fetch", "--base-url", "http://localhost:8080"]).unwrap();
+        assert!(opts.fetch);
+        assert_eq!(opts.base_url.as_deref(), Some("http://localhost:8080"));
+        assert!(parse(&["--submit", "-d", "3", "-p", "2"]).unwrap().submit);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse(&["--day"]).is_err());
+        assert!(parse(&["--day", "7-3"]).is_err());
+        assert!(parse(&["--day", "26"]).is_err());
+        assert!(parse(&["--part", "3"]).is_err());
+        assert!(parse(&["--iterations", "0"]).is_err());
+        assert!(parse(&["--jobs", "0"]).is_err());
+        assert!(parse(&["--timeout", "-1"]).is_err());
+        assert!(parse(&["--bogus"]).is_err());
+        assert!(parse(&["--input", "x.txt", "--input-set", "alice"]).is_err());
+        assert!(parse(&["--answers", "mine.toml", "--input-set", "alice"]).is_err());
+        assert!(parse(&["--watch", "--bench"]).is_err());
+        assert!(parse(&["--watch", "--input", "-"]).is_err());
+        assert!(parse(&["--tui", "--watch"]).is_err());
+        assert!(parse(&["--tui", "--input", "-"]).is_err());
+        assert!(parse(&["--frames", "ansi"]).is_err());
+        assert!(parse(&["-d", "9", "--frames", "png:x.png"]).is_err());
+        assert!(parse(&["-d", "9", "--frames", "ansi", "--bench"]).is_err());
+        assert!(parse(&["--fetch", "-s", "alice"]).is_err());
+        assert!(parse(&["--submit", "-d", "3"]).is_err());
+        assert!(parse(&["--submit", "-d", "3-4", "-p", "1"]).is_err());
+        assert!(parse(&["--submit", "-d", "3", "-p", "1", "--verify"]).is_err());
+        assert!(parse(&["--report", "run.json", "--verify"]).is_err());
+        assert!(parse(&["--report", "run.json", "--bench"]).is_err());
+        assert!(parse(&["new-day"]).is_err());
+        assert!(parse(&["new-day", "0"]).is_err());
+        assert!(parse(&["new-day", "3", "new-day", "4"]).is_err());
+    }
+
+    #[test]
+    fn test_threads() {
+        assert_eq!(Options::default().threads(), None);
+        assert_eq!(parse(&["-j", "4"]).unwrap().threads(), Some(4));
+        assert!(parse(&["--parallel"]).unwrap().threads().unwrap() >= 1);
+    }
+
+    #[test]
+    fn test_selects_part() {
+        let opts = parse(&["--part", "2"]).unwrap();
+        assert!(!opts.selects_part(1));
+        assert!(opts.selects_part(2));
+        assert!(Options::default().selects_part(1));
+    }
+}
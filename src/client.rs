@@ -0,0 +1,365 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+
+use crate::input::InputSet;
+use crate::interface::*;
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+/// Environment variable overriding [`DEFAULT_BASE_URL`].
+pub const BASE_URL_VAR: &str = "ADVENT_BASE_URL";
+/// Environment variable holding the session cookie of the logged-in account.
+pub const SESSION_VAR: &str = "ADVENT_SESSION";
+/// The least time the client leaves between two requests to the server.
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(3);
+
+/// What the server made of a submitted answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Submission {
+    Correct,
+    /// Wrong, possibly with a hint such as "too high".
+    Incorrect { hint: Option<String> },
+    /// Submitted too soon after the last one; `wait` is how long is left.
+    TooSoon { wait: Option<String> },
+    /// The part was already solved, or isn't unlocked yet.
+    WrongLevel,
+    /// A page the client doesn't recognize, reduced to its text.
+    Unknown(String),
+}
+
+impl fmt::Display for Submission {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Submission::Correct => write!(f, "CORRECT"),
+            Submission::Incorrect { hint: Some(hint) } => write!(f, "INCORRECT ({})", hint),
+            Submission::Incorrect { hint: None } => write!(f, "INCORRECT"),
+            Submission::TooSoon { wait: Some(wait) } => write!(f, "TOO SOON (wait {})", wait),
+            Submission::TooSoon { wait: None } => write!(f, "TOO SOON"),
+            Submission::WrongLevel => write!(f, "ALREADY SOLVED OR LOCKED"),
+            Submission::Unknown(text) => write!(f, "UNKNOWN ({})", text),
+        }
+    }
+}
+
+impl Submission {
+    /// Reads the verdict out of the page returned for a submission.
+    pub fn parse(page: &str) -> Submission {
+        let text = strip_tags(page);
+        if text.contains("That's the right answer") {
+            Submission::Correct
+        } else if text.contains("That's not the right answer") {
+            let hint = ["too high", "too low"]
+                .into_iter()
+                .find(|hint| text.contains(hint))
+                .map(|hint| hint.to_string());
+            Submission::Incorrect { hint }
+        } else if text.contains("You gave an answer too recently") {
+            let wait = text
+                .split_once("You have ")
+                .and_then(|(_, rest)| rest.split_once(" left to wait"))
+                .map(|(wait, _)| wait.to_string());
+            Submission::TooSoon { wait }
+        } else if text.contains("You don't seem to be solving the right level") {
+            Submission::WrongLevel
+        } else {
+            Submission::Unknown(text)
+        }
+    }
+}
+
+/// The text of an HTML page with its tags removed and whitespace collapsed.
+fn strip_tags(page: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in page.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Talks to the puzzle server: downloads inputs and submits answers on
+/// behalf of one account, never sending requests closer together than its
+/// minimum interval.
+pub struct Client {
+    agent: ureq::Agent,
+    base_url: String,
+    session: String,
+    min_interval: Duration,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl Client {
+    pub fn new(base_url: &str, session: &str) -> Client {
+        Client {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .user_agent(concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")))
+                .build(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session: session.to_string(),
+            min_interval: DEFAULT_MIN_INTERVAL,
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// A client for `base_url`, or `$ADVENT_BASE_URL`, or the real site,
+    /// logged in with the session cookie in `$ADVENT_SESSION`.
+    pub fn from_env(base_url: Option<&str>) -> Result<Client> {
+        let session = env::var(SESSION_VAR)
+            .with_context(|| format!("Set {} to the session cookie of your account", SESSION_VAR))?;
+        let base_url = base_url
+            .map(str::to_string)
+            .or_else(|| env::var(BASE_URL_VAR).ok())
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        Ok(Client::new(&base_url, &session))
+    }
+
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Client {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Waits until the minimum interval since the last request has passed.
+    fn throttle(&self) {
+        let mut last = self.last_request.lock().unwrap();
+        if let Some(last) = *last {
+            let elapsed = last.elapsed();
+            if elapsed < self.min_interval {
+                thread::sleep(self.min_interval - elapsed);
+            }
+        }
+        *last = Some(Instant::now());
+    }
+
+    fn url(&self, year: u16, day: u8, page: &str) -> String {
+        format!("{}/{}/day/{}/{}", self.base_url, year, day, page)
+    }
+
+    fn cookie(&self) -> String {
+        format!("session={}", self.session)
+    }
+
+    fn read(url: &str, response: std::result::Result<ureq::Response, ureq::Error>) -> Result<String> {
+        match response {
+            Ok(response) => Ok(response.into_string()?),
+            Err(ureq::Error::Status(404, _)) => Err(anyhow!("{} isn't available (404)", url)),
+            Err(ureq::Error::Status(code, _)) => Err(anyhow!(
+                "{} returned {}; is {} still valid?",
+                url,
+                code,
+                SESSION_VAR
+            )),
+            Err(e) => Err(anyhow!("Requesting {}: {}", url, e)),
+        }
+    }
+
+    pub fn fetch_input(&self, year: u16, day: u8) -> Result<String> {
+        let url = self.url(year, day, "input");
+        self.throttle();
+        let response = self.agent.get(&url).set("Cookie", &self.cookie()).call();
+        Client::read(&url, response)
+    }
+
+    pub fn submit(&self, year: u16, day: u8, part: u8, answer: &str) -> Result<Submission> {
+        let url = self.url(year, day, "answer");
+        self.throttle();
+        let response = self
+            .agent
+            .post(&url)
+            .set("Cookie", &self.cookie())
+            .send_form(&[("level", &part.to_string()), ("answer", answer)]);
+        Ok(Submission::parse(&Client::read(&url, response)?))
+    }
+}
+
+/// Downloads the input of every day whose input file doesn't exist yet,
+/// writing it where `inputs` will look for it. Inputs never change, so a
+/// file that is already there is never fetched again. Returns the files
+/// written.
+pub fn fill_missing(
+    client: &Client,
+    year: u16,
+    days: &[Arc<dyn Day>],
+    inputs: &InputSet,
+) -> Result<Vec<PathBuf>> {
+    let mut written = vec![];
+    for day in days {
+        let Some(path) = inputs.path(day.number()) else {
+            continue;
+        };
+        if path.exists() {
+            continue;
+        }
+        let input = client.fetch_input(year, day.number())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Creating {}", dir.display()))?;
+        }
+        fs::write(&path, input).with_context(|| format!("Writing {}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::days::{day01, day02};
+
+    /// A stand-in for the puzzle site. It answers each request with the
+    /// canned response for its path, or a 404, and keeps every request it
+    /// was sent.
+    struct MockServer {
+        url: String,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    fn read_request(stream: &mut TcpStream) -> String {
+        let mut reader = BufReader::new(stream);
+        let mut request = String::new();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            request += &line;
+            if line == "\r\n" || line.is_empty() {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        request + &String::from_utf8(body).unwrap()
+    }
+
+    impl MockServer {
+        fn start(routes: Vec<(&'static str, u16, &'static str)>) -> MockServer {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(vec![]));
+            let seen = Arc::clone(&requests);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let request = read_request(&mut stream);
+                    let path = request.split(' ').nth(1).unwrap_or("").to_string();
+                    let (status, body) = routes
+                        .iter()
+                        .find(|(p, _, _)| *p == path)
+                        .map_or((404, "Not Found"), |&(_, status, body)| (status, body));
+                    write!(
+                        stream,
+                        "HTTP/1.1 {} Canned\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    )
+                    .unwrap();
+                    seen.lock().unwrap().push(request);
+                }
+            });
+            MockServer { url, requests }
+        }
+
+        fn client(&self) -> Client {
+            Client::new(&self.url, "abc123").with_min_interval(Duration::ZERO)
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[test]
+    fn test_fetch_input() {
+        let server = MockServer::start(vec![("/2022/day/1/input", 200, "1000\n2000\n")]);
+        let client = server.client();
+        assert_eq!(client.fetch_input(2022, 1).unwrap(), "1000\n2000\n");
+        let request = server.requests()[0].to_lowercase();
+        assert!(request.starts_with("get /2022/day/1/input "), "{}", request);
+        assert!(request.contains("cookie: session=abc123"), "{}", request);
+
+        let err = client.fetch_input(2022, 2).unwrap_err();
+        assert!(err.to_string().contains("404"), "{}", err);
+    }
+
+    #[test]
+    fn test_fill_missing() {
+        let server = MockServer::start(vec![("/2022/day/1/input", 200, "1000\n")]);
+        let dir = std::env::temp_dir().join(format!("advent-fetch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("input02.txt"), "A Y\n").unwrap();
+        let inputs = InputSet::dir(&dir);
+        let days: Vec<Arc<dyn Day>> = vec![day01::register(), day02::register()];
+
+        let written = fill_missing(&server.client(), 2022, &days, &inputs).unwrap();
+        assert_eq!(written, vec![dir.join("input01.txt")]);
+        assert_eq!(fs::read_to_string(dir.join("input01.txt")).unwrap(), "1000\n");
+        // cached from now on
+        assert!(fill_missing(&server.client(), 2022, &days, &inputs).unwrap().is_empty());
+        assert_eq!(server.requests().len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_submit() {
+        let server = MockServer::start(vec![(
+            "/2022/day/1/answer",
+            200,
+            "<main><article><p>That's not the right answer; your answer is too high.</p></article></main>",
+        )]);
+        let verdict = server.client().submit(2022, 1, 2, "45000").unwrap();
+        assert_eq!(verdict, Submission::Incorrect { hint: Some("too high".to_string()) });
+        let request = server.requests()[0].clone();
+        assert!(request.starts_with("POST /2022/day/1/answer "), "{}", request);
+        assert!(request.ends_with("level=2&answer=45000"), "{}", request);
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let server = MockServer::start(vec![("/2022/day/1/input", 200, "1\n")]);
+        let client = server.client().with_min_interval(Duration::from_millis(50));
+        let start = Instant::now();
+        client.fetch_input(2022, 1).unwrap();
+        client.fetch_input(2022, 1).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_parse_submission() {
+        assert_eq!(
+            Submission::parse("<p>That's the right answer!  You are <em>one gold star</em> closer.</p>"),
+            Submission::Correct
+        );
+        assert_eq!(
+            Submission::parse("<p>That's not the right answer.  If you're stuck...</p>"),
+            Submission::Incorrect { hint: None }
+        );
+        let too_soon = Submission::parse(
+            "<p>You gave an answer too recently; you have to wait after submitting an answer before trying again.  You have 4m 12s left to wait.</p>",
+        );
+        assert_eq!(too_soon, Submission::TooSoon { wait: Some("4m 12s".to_string()) });
+        assert_eq!(too_soon.to_string(), "TOO SOON (wait 4m 12s)");
+        assert_eq!(
+            Submission::parse("<p>You don't seem to be solving the right level.  Did you already complete it?</p>"),
+            Submission::WrongLevel
+        );
+        assert_eq!(Submission::parse("<p>Hello</p>"), Submission::Unknown("Hello".to_string()));
+    }
+}
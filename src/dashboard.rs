@@ -0,0 +1,404 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use crate::answers::Answers;
+use crate::cli::Options;
+use crate::input::InputSet;
+use crate::interface::*;
+use crate::runner::{run_day, DayResult};
+use crate::verify::Verdict;
+
+const COLUMNS: u8 = 5;
+const CELL_WIDTH: usize = 20;
+
+/// Where a day is in the dashboard's run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// No module for this day has been written.
+    Missing,
+    /// Left out by `--day`; can still be run by hand.
+    Skipped,
+    /// Slow and left out for want of `--include-slow`; can still be run by hand.
+    Slow,
+    Pending,
+    Running,
+    /// Every part finished and none contradicts the answer registry.
+    Pass,
+    /// A part failed, timed out or disagrees with the answer registry.
+    Fail,
+}
+
+impl Status {
+    fn label(&self) -> &'static str {
+        match self {
+            Status::Missing => "",
+            Status::Skipped => "skip",
+            Status::Slow => "slow",
+            Status::Pending => "wait",
+            Status::Running => "run",
+            Status::Pass => "PASS",
+            Status::Fail => "FAIL",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Status::Missing | Status::Skipped => Color::DarkGrey,
+            Status::Slow => Color::Magenta,
+            Status::Pending => Color::Grey,
+            Status::Running => Color::Yellow,
+            Status::Pass => Color::Green,
+            Status::Fail => Color::Red,
+        }
+    }
+}
+
+/// Sent by the worker threads as days start and finish.
+pub enum Update {
+    Started(u8),
+    Finished(DayResult),
+}
+
+struct Tile {
+    day: Option<Arc<dyn Day>>,
+    status: Status,
+    /// Part number, answer or error, and verdict of the last run.
+    parts: Vec<(u8, String, Verdict)>,
+    elapsed: Option<Duration>,
+}
+
+/// The state behind the dashboard: one tile per day of the calendar and the
+/// selected day. Drawing is left to [`run`].
+pub struct Dashboard {
+    tiles: Vec<Tile>,
+    answers: Answers,
+    selected: u8,
+}
+
+impl Dashboard {
+    /// Lays out `days` on the calendar. Days `opts` selects start out
+    /// pending; the others are shown but left alone.
+    pub fn new(days: &[Arc<dyn Day>], opts: &Options, answers: Answers) -> Dashboard {
+        let tiles = (1..=25)
+            .map(|n| {
+                let day = days.iter().find(|d| d.number() == n).cloned();
+                let status = match &day {
+                    None => Status::Missing,
+                    Some(day) if opts.selects_day(day.as_ref()) => Status::Pending,
+                    Some(day) if day.slow() && !opts.include_slow => Status::Slow,
+                    Some(_) => Status::Skipped,
+                };
+                Tile {
+                    day,
+                    status,
+                    parts: vec![],
+                    elapsed: None,
+                }
+            })
+            .collect();
+        Dashboard {
+            tiles,
+            answers,
+            selected: 1,
+        }
+    }
+
+    fn tile(&self, day: u8) -> &Tile {
+        &self.tiles[day as usize - 1]
+    }
+
+    fn tile_mut(&mut self, day: u8) -> &mut Tile {
+        &mut self.tiles[day as usize - 1]
+    }
+
+    pub fn status(&self, day: u8) -> Status {
+        self.tile(day).status
+    }
+
+    pub fn selected(&self) -> u8 {
+        self.selected
+    }
+
+    /// The days waiting for their first run.
+    pub fn pending(&self) -> Vec<Arc<dyn Day>> {
+        self.tiles
+            .iter()
+            .filter(|t| t.status == Status::Pending)
+            .filter_map(|t| t.day.clone())
+            .collect()
+    }
+
+    /// Marks the selected day as pending and returns it, unless it has no
+    /// module or is already queued or running.
+    pub fn rerun_selected(&mut self) -> Option<Arc<dyn Day>> {
+        let tile = self.tile_mut(self.selected);
+        if matches!(tile.status, Status::Pending | Status::Running) {
+            return None;
+        }
+        let day = tile.day.clone()?;
+        tile.status = Status::Pending;
+        Some(day)
+    }
+
+    pub fn apply(&mut self, update: Update) {
+        match update {
+            Update::Started(day) => self.tile_mut(day).status = Status::Running,
+            Update::Finished(result) => {
+                let parts: Vec<_> = result
+                    .parts
+                    .into_iter()
+                    .map(|part| {
+                        let shown = match &part.answer {
+                            Ok(answer) => answer.to_string(),
+                            Err(e) => format!("{:#}", e),
+                        };
+                        let expected = self.answers.get(result.day, part.part);
+                        let verdict = Verdict::check(expected, part.answer);
+                        (part.part, shown, verdict)
+                    })
+                    .collect();
+                let tile = self.tile_mut(result.day);
+                tile.status = match parts.iter().any(|(_, _, verdict)| verdict.is_failure()) {
+                    true => Status::Fail,
+                    false => Status::Pass,
+                };
+                tile.parts = parts;
+                tile.elapsed = Some(result.elapsed);
+            }
+        }
+    }
+
+    /// Moves the selection by `dx` columns and `dy` rows, staying on the
+    /// calendar.
+    pub fn move_selection(&mut self, dx: i8, dy: i8) {
+        let idx = (self.selected - 1) as i8;
+        let (row, col) = (idx / COLUMNS as i8 + dy, idx % COLUMNS as i8 + dx);
+        if (0..COLUMNS as i8).contains(&col) && row >= 0 {
+            let day = (row * COLUMNS as i8 + col + 1) as u8;
+            if day <= 25 {
+                self.selected = day;
+            }
+        }
+    }
+
+    /// The text of a day's tile, e.g. `07 PASS    12.3ms`.
+    pub fn cell(&self, day: u8) -> String {
+        let tile = self.tile(day);
+        let elapsed = match (tile.status, tile.elapsed) {
+            (Status::Pass | Status::Fail, Some(elapsed)) => format!("{:.1?}", elapsed),
+            _ => String::new(),
+        };
+        format!("{:02} {:<4} {:>10}", day, tile.status.label(), elapsed)
+    }
+
+    /// What is known about the selected day, one line per entry.
+    pub fn details(&self) -> Vec<String> {
+        let tile = self.tile(self.selected);
+        let mut lines = vec![format!("Day {:02}: {:?}", self.selected, tile.status)];
+        for (part, shown, verdict) in &tile.parts {
+            let verdict = match verdict {
+                Verdict::Pass | Verdict::Missing { .. } | Verdict::Error(_) => String::new(),
+                verdict => format!("  {}", verdict),
+            };
+            if shown.contains('\n') {
+                lines.push(format!("  part {}:{}", part, verdict));
+                lines.extend(shown.lines().map(|line| format!("    {}", line)));
+            } else {
+                lines.push(format!("  part {}: {}{}", part, shown, verdict));
+            }
+        }
+        lines
+    }
+}
+
+/// Runs `days` on `threads` background threads, reporting to `tx`.
+fn spawn(
+    days: Vec<Arc<dyn Day>>,
+    inputs: &InputSet,
+    opts: &Arc<Options>,
+    threads: usize,
+    tx: &mpsc::Sender<Update>,
+) {
+    let days = Arc::new(days);
+    let next = Arc::new(AtomicUsize::new(0));
+    for _ in 0..threads.min(days.len()) {
+        let (days, next, inputs, opts, tx) = (
+            Arc::clone(&days),
+            Arc::clone(&next),
+            inputs.clone(),
+            Arc::clone(opts),
+            tx.clone(),
+        );
+        thread::spawn(move || {
+            while let Some(day) = days.get(next.fetch_add(1, Ordering::Relaxed)) {
+                // The dashboard has gone away once sending fails.
+                if tx.send(Update::Started(day.number())).is_err() {
+                    break;
+                }
+                if tx.send(Update::Finished(run_day(day, &inputs, &opts))).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Puts the terminal back the way it was, even if drawing fails.
+struct Screen;
+
+impl Screen {
+    fn enter() -> Result<Screen> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw(out: &mut impl Write, dashboard: &Dashboard) -> Result<()> {
+    queue!(out, Clear(ClearType::All), MoveTo(0, 0), Print("Advent of Code 2022"))?;
+    for day in 1..=25 {
+        let (row, col) = ((day - 1) / COLUMNS, (day - 1) % COLUMNS);
+        queue!(
+            out,
+            MoveTo(col as u16 * CELL_WIDTH as u16, 2 + row as u16),
+            SetForegroundColor(dashboard.status(day).color())
+        )?;
+        if day == dashboard.selected() {
+            queue!(out, SetAttribute(Attribute::Reverse))?;
+        }
+        queue!(
+            out,
+            Print(format!("{:<w$}", dashboard.cell(day), w = CELL_WIDTH - 1)),
+            SetAttribute(Attribute::Reset),
+            ResetColor
+        )?;
+    }
+    let mut y = 3 + (25 / COLUMNS as u16);
+    for line in dashboard.details() {
+        queue!(out, MoveTo(0, y), Print(line))?;
+        y += 1;
+    }
+    queue!(
+        out,
+        MoveTo(0, y + 1),
+        SetForegroundColor(Color::DarkGrey),
+        Print("arrows: select   enter/r: run selected day   q: quit"),
+        ResetColor
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Shows every registered day on a calendar and runs the selected ones in
+/// the background, updating the tiles as they finish. Any day can be run
+/// again from the keyboard. Returns when the user quits.
+pub fn run(days: &[Arc<dyn Day>], inputs: &InputSet, opts: &Options) -> Result<()> {
+    let answers = Answers::load(&inputs.answers_path(&opts.answers))?;
+    let mut dashboard = Dashboard::new(days, opts, answers);
+    let opts = Arc::new(opts.clone());
+    let (tx, rx) = mpsc::channel();
+    spawn(dashboard.pending(), inputs, &opts, opts.threads().unwrap_or(1), &tx);
+
+    let _screen = Screen::enter()?;
+    let mut out = io::stdout();
+    loop {
+        while let Ok(update) = rx.try_recv() {
+            dashboard.apply(update);
+        }
+        draw(&mut out, &dashboard)?;
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Left => dashboard.move_selection(-1, 0),
+            KeyCode::Right => dashboard.move_selection(1, 0),
+            KeyCode::Up => dashboard.move_selection(0, -1),
+            KeyCode::Down => dashboard.move_selection(0, 1),
+            KeyCode::Enter | KeyCode::Char('r') => {
+                if let Some(day) = dashboard.rerun_selected() {
+                    spawn(vec![day], inputs, &opts, 1, &tx);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::{day06, day15};
+
+    #[test]
+    fn test_dashboard() {
+        let days = vec![day06::register(), day15::register()];
+        let mut answers = Answers::default();
+        answers.insert(6, 1, &Answer::Number(7));
+        answers.insert(6, 2, &Answer::Number(20));
+        let opts = Options::default();
+        let mut dashboard = Dashboard::new(&days, &opts, answers);
+        assert_eq!(dashboard.status(1), Status::Missing);
+        assert_eq!(dashboard.status(6), Status::Pending);
+        assert_eq!(dashboard.status(15), Status::Slow);
+        assert_eq!(dashboard.pending().len(), 1);
+
+        dashboard.apply(Update::Started(6));
+        assert_eq!(dashboard.status(6), Status::Running);
+        let inputs = InputSet::lines("test", vec!["mjqjpqmgbljsphdztnvjfqwrcgsmlb".to_string()]);
+        dashboard.apply(Update::Finished(run_day(&days[0], &inputs, &opts)));
+        assert_eq!(dashboard.status(6), Status::Fail);
+        assert!(dashboard.cell(6).starts_with("06 FAIL "));
+
+        dashboard.move_selection(0, 1);
+        assert_eq!(dashboard.selected(), 6);
+        assert_eq!(
+            dashboard.details(),
+            vec![
+                "Day 06: Fail",
+                "  part 1: 7",
+                "  part 2: 19  FAIL (expected \"20\", got \"19\")"
+            ]
+        );
+        assert!(dashboard.rerun_selected().is_some());
+        assert!(dashboard.rerun_selected().is_none());
+        assert_eq!(dashboard.status(6), Status::Pending);
+    }
+
+    #[test]
+    fn test_move_selection() {
+        let mut dashboard = Dashboard::new(&[], &Options::default(), Answers::default());
+        dashboard.move_selection(-1, 0);
+        dashboard.move_selection(0, -1);
+        assert_eq!(dashboard.selected(), 1);
+        dashboard.move_selection(4, 4);
+        assert_eq!(dashboard.selected(), 25);
+        dashboard.move_selection(1, 0);
+        dashboard.move_selection(0, 1);
+        assert_eq!(dashboard.selected(), 25);
+        dashboard.move_selection(-2, -1);
+        assert_eq!(dashboard.selected(), 18);
+    }
+}
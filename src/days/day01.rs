@@ -2,18 +2,26 @@ use crate::interface::*;
 
 pub struct Day01;
 
-fn elves(v: &Vec<String>) -> Result<Vec<i32>> {
+register_day!(Day01);
+
+fn elves(v: &[String]) -> Result<Vec<i32>> {
     let mut res = vec![];
-    let mut rolling = 0;
-    for s in v {
+    let mut rolling: i32 = 0;
+    for (n, s) in v.iter().enumerate() {
         if s.is_empty() {
             res.push(rolling);
             rolling = 0;
         } else {
-            let i: i32 = s.parse()?;
-            rolling += i;
+            let i: i32 = parse_field(s, 0..s.len(), "a calorie count").map_err(|e| e.at_line(n + 1))?;
+            rolling = rolling
+                .checked_add(i)
+                .ok_or_else(|| ParseError::new("an elf carrying fewer calories", s, 0).at_line(n + 1))?;
         }
     }
+    // the last elf isn't followed by a blank line
+    if v.last().is_some_and(|s| !s.is_empty()) {
+        res.push(rolling);
+    }
     Ok(res)
 }
 
@@ -29,21 +37,22 @@ pub fn top_n(elves: Vec<i32>, count: usize) -> Vec<i32> {
     top
 }
 
-impl Day for Day01 {
+impl DayParsed for Day01 {
+    type Parsed = Vec<i32>;
+
     fn number(&self) -> u8 {
         1
     }
-    fn part01(&self) -> Result<()> {
-        let vals = elves(&self.input()?)?;
-        let top = top_n(vals, 1);
-        println!("{}", top[0]);
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<Vec<i32>> {
+        elves(input)
     }
-    fn part02(&self) -> Result<()> {
-        let vals = elves(&self.input()?)?;
-        let top = top_n(vals, 3);
-        println!("{}", top[0] + top[1] + top[2]);
-        Ok(())
+    fn part01(&self, vals: &Vec<i32>) -> Result<Answer> {
+        let top = top_n(vals.clone(), 1);
+        Ok(top[0].into())
+    }
+    fn part02(&self, vals: &Vec<i32>) -> Result<Answer> {
+        let top = top_n(vals.clone(), 3);
+        Ok((top[0] + top[1] + top[2]).into())
     }
 }
 
@@ -72,6 +81,24 @@ mod tests {
         assert_eq!(45000, top_n(elves, 3).into_iter().sum::<i32>());
     }
 
+    #[test]
+    fn test_elves() {
+        let input: Vec<String> = vec!["1000".into(), "2000".into(), "".into(), "4000".into()];
+        assert_eq!(elves(&input).unwrap(), vec![3000, 4000]);
+        let input: Vec<String> = vec!["1000".into(), "".into()];
+        assert_eq!(elves(&input).unwrap(), vec![1000]);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let input: Vec<String> = vec!["1000".into(), "".into(), "20x0".into()];
+        let err = elves(&input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 1: expected a calorie count, found `20x0`"
+        );
+    }
+
     #[test]
     fn test_string() {
         assert_eq!("input01.txt", format!("input{:02}.txt", 1));
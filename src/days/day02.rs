@@ -1,19 +1,19 @@
 use crate::interface::*;
 
 #[derive(Copy, Clone, Debug)]
-enum RPS {
+pub enum RPS {
     Rock,
     Paper,
     Scissors,
 }
 
 impl RPS {
-    fn from_char(c: char) -> RPS {
+    fn from_char(c: char) -> Option<RPS> {
         match c {
-            'A' => RPS::Rock,
-            'B' => RPS::Paper,
-            'C' => RPS::Scissors,
-            _ => unreachable!("invalid RPS: '{}'", c),
+            'A' => Some(RPS::Rock),
+            'B' => Some(RPS::Paper),
+            'C' => Some(RPS::Scissors),
+            _ => None,
         }
     }
     fn value(&self) -> i32 {
@@ -30,19 +30,19 @@ impl RPS {
 }
 
 #[derive(Copy, Clone, Debug)]
-enum Strat {
+pub enum Strat {
     Lose,
     Draw,
     Win,
 }
 
 impl Strat {
-    fn from_char(c: char) -> Strat {
+    fn from_char(c: char) -> Option<Strat> {
         match c {
-            'X' => Self::Lose,
-            'Y' => Self::Draw,
-            'Z' => Self::Win,
-            _ => unreachable!("invalid strat: '{}'", c),
+            'X' => Some(Self::Lose),
+            'Y' => Some(Self::Draw),
+            'Z' => Some(Self::Win),
+            _ => None,
         }
     }
     fn pick_move(&self, opponent: RPS) -> RPS {
@@ -59,17 +59,27 @@ impl Strat {
     }
 }
 
-fn read_strategies(fname: &str) -> Result<Vec<(RPS, Strat)>> {
-    let lines = read_lines(fname)?;
-    lines
-        .into_iter()
-        .map(|s| -> Result<(RPS, Strat)> {
-            let first = s.chars().next().map(|c| RPS::from_char(c)).unwrap();
-            let second = s.chars().nth(2).map(|c| Strat::from_char(c)).unwrap();
-
-            Ok((first, second))
-        })
-        .collect()
+fn read_strategy(s: &str) -> Result<(RPS, Strat), ParseError> {
+    let mut chars = s.chars();
+    let first = chars
+        .next()
+        .and_then(RPS::from_char)
+        .ok_or_else(|| ParseError::new("`A`, `B` or `C`", s, 0))?;
+    if chars.next() != Some(' ') {
+        return Err(ParseError::new("a space", s, 1));
+    }
+    let second = chars
+        .next()
+        .and_then(Strat::from_char)
+        .ok_or_else(|| ParseError::new("`X`, `Y` or `Z`", s, 2))?;
+    if chars.next().is_some() {
+        return Err(ParseError::new("end of line", s, 3));
+    }
+    Ok((first, second))
+}
+
+fn read_strategies(lines: &[String]) -> Result<Vec<(RPS, Strat)>> {
+    Ok(parse_each(lines, read_strategy)?)
 }
 
 fn map_strat((other, strat): (RPS, Strat)) -> (RPS, RPS) {
@@ -93,40 +103,59 @@ fn score((opp, me): (RPS, RPS)) -> i32 {
 }
 
 pub struct Day02;
-impl Day for Day02 {
+
+register_day!(Day02);
+
+impl DayParsed for Day02 {
+    type Parsed = Vec<(RPS, Strat)>;
+
     fn number(&self) -> u8 {
         2
     }
-    fn part01(&self) -> Result<()> {
-        let strats = read_strategies("input02.txt")?;
-        let total: i32 = strats.into_iter().map(map_strat).map(score).sum();
-        println!("{}", total);
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<Self::Parsed> {
+        read_strategies(input)
+    }
+    fn part01(&self, strats: &Self::Parsed) -> Result<Answer> {
+        let total: i32 = strats.iter().copied().map(map_strat).map(score).sum();
+        Ok(total.into())
     }
 
-    fn part02(&self) -> Result<()> {
-        let strats = read_strategies("input02.txt")?;
-        let total: i32 = strats.into_iter().map(execute_strat).map(score).sum();
-        println!("{}", total);
-        Ok(())
+    fn part02(&self, strats: &Self::Parsed) -> Result<Answer> {
+        let total: i32 = strats.iter().copied().map(execute_strat).map(score).sum();
+        Ok(total.into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures;
+
+    fn test_data() -> Vec<String> {
+        fixtures::load(2).unwrap().remove(0).input
+    }
 
     #[test]
     fn test_p1() {
-        let strats = read_strategies("test02.txt").unwrap();
+        let strats = read_strategies(&test_data()).unwrap();
         let total: i32 = strats.into_iter().map(map_strat).map(score).sum();
         assert_eq!(total, 15)
     }
 
     #[test]
     fn test_p2() {
-        let strats = read_strategies("test02.txt").unwrap();
+        let strats = read_strategies(&test_data()).unwrap();
         let total: i32 = strats.into_iter().map(execute_strat).map(score).sum();
         assert_eq!(total, 12)
     }
+
+    #[test]
+    fn test_parse_error() {
+        let err = read_strategies(&split_str("A Y\nB X\nC W")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 3: expected `X`, `Y` or `Z`, found `W`"
+        );
+        assert!(read_strategies(&split_str("A  Y")).is_err());
+    }
 }
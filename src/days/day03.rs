@@ -1,8 +1,11 @@
 use crate::interface::*;
+use anyhow::anyhow;
 
 pub struct Day03;
 
-fn find_common_item(items: Vec<&str>) -> u8 {
+register_day!(Day03);
+
+fn find_common_item(items: Vec<&str>) -> Option<u8> {
     let mut counts: [usize; 128] = [0; 128];
     let count = items.len();
     for coll in items {
@@ -18,18 +21,31 @@ fn find_common_item(items: Vec<&str>) -> u8 {
     }
     for i in 0..128 {
         if counts[i] == count {
-            return i as u8;
+            return Some(i as u8);
         }
     }
-    0
+    None
 }
 
-fn find_halves(sack: &str) -> u8 {
+fn find_halves(sack: &str) -> Option<u8> {
     let (first, second) = sack.split_at(sack.len() / 2);
     let items = vec![first, second];
     find_common_item(items)
 }
 
+fn read_sack(s: &str) -> Result<String, ParseError> {
+    if let Some((i, _)) = s.char_indices().find(|(_, c)| !c.is_ascii_alphabetic()) {
+        return Err(ParseError::new("an item letter", s, i));
+    }
+    if s.len() % 2 != 0 {
+        return Err(ParseError::new("as many items in each compartment", s, s.len()));
+    }
+    if find_halves(s).is_none() {
+        return Err(ParseError::new("an item in both compartments", s, 0));
+    }
+    Ok(s.to_string())
+}
+
 fn priority(item: u8) -> u8 {
     if item >= b'a' {
         return *(&item) - b'a' + 1;
@@ -37,35 +53,34 @@ fn priority(item: u8) -> u8 {
     return item - b'A' + 27;
 }
 
-impl Day for Day03 {
+impl DayParsed for Day03 {
+    type Parsed = Vec<String>;
+
     fn number(&self) -> u8 {
         3
     }
-    fn part01(&self) -> Result<()> {
-        let sacks = self.input()?;
+    fn parse(&self, input: &[String]) -> Result<Vec<String>> {
+        Ok(parse_each(input, read_sack)?)
+    }
+    fn part01(&self, sacks: &Vec<String>) -> Result<Answer> {
         let total: i32 = sacks
-            .into_iter()
-            .map(|s| find_halves(s.as_str()))
-            .map(priority)
-            .map(i32::from)
-            .sum();
-        println!("{}", total);
-        Ok(())
+            .iter()
+            .map(|s| find_halves(s.as_str()).map(priority).map(i32::from))
+            .sum::<Option<i32>>()
+            .ok_or_else(|| anyhow!("A sack has no item in both compartments"))?;
+        Ok(total.into())
     }
-    fn part02(&self) -> Result<()> {
-        let sacks = self.input()?;
-        let groups = sacks.chunks(3);
-        let sum: i32 = groups
-            .into_iter()
-            .map(|x| {
-                let v: Vec<&str> = x.into_iter().map(|s| s.as_str()).collect();
-                find_common_item(v)
-            })
-            .map(priority)
-            .map(i32::from)
-            .sum();
-        println!("{}", sum);
-        Ok(())
+    fn part02(&self, sacks: &Vec<String>) -> Result<Answer> {
+        let mut sum: i32 = 0;
+        for (n, group) in sacks.chunks(3).enumerate() {
+            if group.len() < 3 {
+                Err(anyhow!("The last group has only {} sacks", group.len()))?;
+            }
+            let v: Vec<&str> = group.iter().map(|s| s.as_str()).collect();
+            let badge = find_common_item(v).ok_or_else(|| anyhow!("Group {} has no badge in common", n + 1))?;
+            sum += i32::from(priority(badge));
+        }
+        Ok(sum.into())
     }
 }
 
@@ -89,7 +104,7 @@ mod tests {
         let sacks = test_vals();
         let total: i32 = sacks
             .into_iter()
-            .map(find_halves)
+            .map(|s| find_halves(s).unwrap())
             .debug("halves")
             .map(priority)
             .debug("priority")
@@ -103,10 +118,24 @@ mod tests {
         let sum: i32 = sacks
             .chunks(3)
             .into_iter()
-            .map(|x| find_common_item(x.to_vec()))
+            .map(|x| find_common_item(x.to_vec()).unwrap())
             .map(priority)
             .map(i32::from)
             .sum();
         assert_eq!(70, sum);
     }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse_each(&split_str("vJrv\nab1c"), read_sack).unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 3: expected an item letter, found `1c`");
+        let err = read_sack("abcab").unwrap_err();
+        assert_eq!(err.to_string(), "column 6: expected as many items in each compartment, found end of line");
+        let err = read_sack("ab").unwrap_err();
+        assert_eq!(err.to_string(), "column 1: expected an item in both compartments, found `ab`");
+        assert!(read_sack("").is_err());
+        let sacks = vec!["aa".to_string(), "bb".to_string(), "cc".to_string()];
+        assert!(DayParsed::part02(&Day03, &sacks).is_err());
+        assert!(DayParsed::part02(&Day03, &sacks[..2].to_vec()).is_err());
+    }
 }
@@ -1,85 +1,90 @@
+use std::fmt;
 use std::str::FromStr;
 
 use crate::interface::*;
-use anyhow::anyhow;
-use lazy_static::lazy_static;
-use regex::Regex;
+use crate::intervals::IntervalSet;
+use crate::parse::{lit, number, parse_line, PResult};
+use nom::sequence::separated_pair;
 
 pub struct Day04;
 
+register_day!(Day04);
+
+#[derive(Debug, PartialEq, Eq)]
 struct Assignment {
     b: u32,
     e: u32,
 }
 
-struct AssigmentPair {
+#[derive(Debug, PartialEq, Eq)]
+pub struct AssigmentPair {
     first: Assignment,
     second: Assignment,
 }
 
+impl fmt::Display for AssigmentPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{},{}-{}", self.first.b, self.first.e, self.second.b, self.second.e)
+    }
+}
+
+fn assignment(s: &str) -> PResult<'_, Assignment> {
+    let (s, b) = number("a section number")(s)?;
+    let (s, _) = lit("-")(s)?;
+    let (s, e) = number("a section number")(s)?;
+    Ok((s, Assignment { b, e }))
+}
+
 impl FromStr for AssigmentPair {
-    type Err = anyhow::Error;
-    fn from_str(s: &str) -> Result<Self> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"^(\d+)-(\d+),(\d+)-(\d+)$").unwrap();
-        }
-        match RE.captures_iter(s).next() {
-            Some(x) => Ok(AssigmentPair {
-                first: Assignment {
-                    b: x[1].parse().unwrap(),
-                    e: x[2].parse().unwrap(),
-                },
-                second: Assignment {
-                    b: x[3].parse().unwrap(),
-                    e: x[4].parse().unwrap(),
-                },
-            }),
-            None => Err(anyhow!("Invalid input: {}", s)),
-        }
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let (first, second) = parse_line(s, separated_pair(assignment, lit(","), assignment))?;
+        Ok(AssigmentPair { first, second })
+    }
+}
+
+impl Assignment {
+    fn sections(&self) -> IntervalSet {
+        IntervalSet::from(self.b as i64..=self.e as i64)
     }
 }
 
 fn full_overlap(pair: &AssigmentPair) -> bool {
-    let b1 = &pair.first.b;
-    let e1 = &pair.first.e;
-    let b2 = &pair.second.b;
-    let e2 = &pair.second.e;
-    (b1 <= b2 && e1 >= e2) || (b2 <= b1 && e2 >= e1)
+    let (first, second) = (pair.first.sections(), pair.second.sections());
+    first.is_superset(&second) || second.is_superset(&first)
 }
 
 fn partial_overlap(pair: &AssigmentPair) -> bool {
-    let b1 = &pair.first.b;
-    let e1 = &pair.first.e;
-    let b2 = &pair.second.b;
-    let e2 = &pair.second.e;
-    !(e1 < b2 || e2 < b1)
+    !pair.first.sections().intersection(&pair.second.sections()).is_empty()
 }
 
-impl DayParsed for Day04 {}
+impl DayParsed for Day04 {
+    type Parsed = Vec<AssigmentPair>;
 
-impl Day for Day04 {
     fn number(&self) -> u8 {
         4
     }
-    fn part01(&self) -> Result<()> {
-        let overlapping = self.input_as::<AssigmentPair>()?.into_iter()
-            .filter(full_overlap)
+    fn parse(&self, input: &[String]) -> Result<Self::Parsed> {
+        parse_lines(input)
+    }
+    fn part01(&self, pairs: &Self::Parsed) -> Result<Answer> {
+        let overlapping = pairs.iter()
+            .filter(|p| full_overlap(p))
             .count();
-        println!("{}", overlapping);
-        Ok(())
+        Ok(overlapping.into())
     }
-    fn part02(&self) -> Result<()> {
-        let overlapping = self.input_as::<AssigmentPair>()?.into_iter()
-            .filter(partial_overlap)
+    fn part02(&self, pairs: &Self::Parsed) -> Result<Answer> {
+        let overlapping = pairs.iter()
+            .filter(|p| partial_overlap(p))
             .count();
-        println!("{}", overlapping);
-        Ok(())
+        Ok(overlapping.into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     fn test_vals() -> Vec<&'static str> {
         vec![
@@ -105,4 +110,31 @@ mod tests {
             .count();
         assert_eq!(overlapping, 4);
     }
+
+    #[test]
+    fn test_parse_error() {
+        let err = parse_lines::<AssigmentPair>(&split_str("2-4,6-8\n2-3;4-5")).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ParseError>().map(|e| (e.line, e.column)),
+            Some((Some(2), 4))
+        );
+        let err = "1-99999999999,2-3".parse::<AssigmentPair>().err().unwrap();
+        assert_eq!(err.column, 3);
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_never_panics(s in "\\PC*|[0-9-,]{0,30}") {
+            let _ = s.parse::<AssigmentPair>();
+        }
+
+        #[test]
+        fn test_display_round_trip(b1: u32, e1: u32, b2: u32, e2: u32) {
+            let pair = AssigmentPair {
+                first: Assignment { b: b1, e: e1 },
+                second: Assignment { b: b2, e: e2 },
+            };
+            prop_assert_eq!(pair.to_string().parse::<AssigmentPair>().unwrap(), pair);
+        }
+    }
 }
@@ -1,86 +1,151 @@
-use std::str::FromStr;
-
 use crate::interface::*;
-
-use lazy_static::lazy_static;
-use regex::Regex;
+use crate::parse::{blocks, expect, failure, lit, number, parse_line, separated, PResult};
+use anyhow::anyhow;
+use nom::{
+    branch::alt,
+    character::complete::{satisfy, space0},
+    combinator::map,
+    sequence::{delimited, preceded, tuple},
+};
 
 pub struct Day05;
 
+register_day!(Day05);
+
 struct Containers {
     stacks: Vec<Vec<char>>,
 }
 
-impl Containers {
-    fn read(inputs: Vec<&str>, batch: bool) -> Containers {
-        let mut stacks: Vec<Vec<char>> = vec![];
-        lazy_static! {
-            static ref C_RE: Regex = Regex::from_str(r"(\[(?P<id>[A-Z])\])|( {4})").unwrap();
-            static ref M_RE: Regex =
-                Regex::from_str(r"move (?P<count>\d+) from (?P<src>\d) to (?P<dest>\d)").unwrap();
+struct Move {
+    count: usize,
+    src: usize,
+    dest: usize,
+}
+
+/// The starting stacks and the moves the crane makes.
+pub struct Procedure {
+    stacks: Vec<Vec<char>>,
+    moves: Vec<Move>,
+}
+
+/// A row of the drawing, e.g. `[Z] [M] [P]`, with `None` for each gap.
+fn crate_row(s: &str) -> PResult<'_, Vec<Option<char>>> {
+    let letter = |s| expect("a crate letter", satisfy(|c| c.is_ascii_uppercase()))(s);
+    let gap = map(lit("   "), |_| None);
+    let stacked = map(delimited(lit("["), letter, lit("]")), Some);
+    separated(" ", expect("a crate like `[A]`", alt((stacked, gap))))(s)
+}
+
+/// The stack numbers under the drawing, e.g. ` 1   2   3`.
+fn stack_labels(s: &str) -> PResult<'_, Vec<usize>> {
+    preceded(space0, separated("   ", number("a stack number")))(s)
+}
+
+fn parse_move(line: &str, stack_count: usize) -> Result<Move, ParseError> {
+    let stack = |s| -> PResult<'_, usize> {
+        let (rest, n) = number("a stack number")(s)?;
+        if n == 0 || n > stack_count {
+            return Err(failure(s, format!("a stack between 1 and {}", stack_count)));
         }
-        for i in &inputs {
-            for (idx, contents) in C_RE.captures_iter(i).enumerate() {
-                if stacks.len() <= idx {
-                    stacks.push(vec![]);
-                }
-                match contents.name("id") {
-                    Some(id) => stacks[idx].push(i.chars().nth(id.start()).unwrap()),
-                    None => (),
+        Ok((rest, n))
+    };
+    let (count, src, dest) = parse_line(
+        line,
+        tuple((
+            preceded(lit("move "), number("a crate count")),
+            preceded(lit(" from "), stack),
+            preceded(lit(" to "), stack),
+        )),
+    )?;
+    Ok(Move { count, src, dest })
+}
+
+impl Procedure {
+    /// The drawing of the stacks, then a blank line, then one move per line.
+    fn parse(inputs: Vec<&str>) -> Result<Procedure> {
+        let mut blocks = blocks(&inputs);
+        let (first, drawing) = blocks.next().ok_or_else(|| anyhow!("No drawing of the stacks"))?;
+        let (labels, rows) = drawing.split_last().unwrap();
+        let at = |i: usize| move |e: ParseError| e.at_line(first + i + 1);
+        let stack_count = parse_line(labels.trim_end(), stack_labels).map_err(at(rows.len()))?.len();
+        let mut stacks: Vec<Vec<char>> = vec![vec![]; stack_count];
+        for (i, row) in rows.iter().enumerate().rev() {
+            let crates = parse_line(row.trim_end(), crate_row).map_err(at(i))?;
+            if crates.len() > stack_count {
+                let expected = format!("at most {} stacks", stack_count);
+                Err(ParseError::new(expected, row, stack_count * 4).at_line(first + i + 1))?;
+            }
+            for (idx, id) in crates.into_iter().enumerate() {
+                stacks[idx].extend(id);
+            }
+        }
+        // follow the heights of the stacks so no move can take more crates
+        // than there are, whichever crane runs it
+        let mut heights: Vec<usize> = stacks.iter().map(|s| s.len()).collect();
+        let mut moves = vec![];
+        for (first, block) in blocks {
+            for (i, line) in block.iter().enumerate() {
+                let m = parse_move(line, stack_count).map_err(|e| e.at_line(first + i + 1))?;
+                if m.count > heights[m.src - 1] {
+                    let expected = format!("at most {} crates", heights[m.src - 1]);
+                    Err(ParseError::new(expected, line, "move ".len()).at_line(first + i + 1))?;
                 }
+                heights[m.src - 1] -= m.count;
+                heights[m.dest - 1] += m.count;
+                moves.push(m);
             }
         }
-        for i in 0..stacks.len() {
-            stacks[i].reverse();
+        if let Some(empty) = heights.iter().position(|&h| h == 0) {
+            Err(anyhow!("Stack {} has no crate on top at the end", empty + 1))?;
         }
-        for i in inputs {
-            match M_RE.captures(i) {
-                None => (),
-                Some(g) => {
-                    let src: usize = g["src"].parse().unwrap();
-                    let dest: usize = g["dest"].parse().unwrap();
-                    let count: usize = g["count"].parse().unwrap();
-                    if batch {
-                        let split = stacks[src - 1].len() - count;
-                        let mut crane: Vec<_> = stacks[src - 1].split_off(split);
-                        stacks[dest - 1].append(&mut crane);
-                    } else {
-                        for _ in 0..count {
-                            let crane_contents = stacks[src - 1].pop().unwrap();
-                            stacks[dest - 1].push(crane_contents);
-                        }
-                    }
+        Ok(Procedure { stacks, moves })
+    }
+
+    fn run(&self, batch: bool) -> Containers {
+        let mut stacks = self.stacks.clone();
+        for &Move { count, src, dest } in &self.moves {
+            if batch {
+                let split = stacks[src - 1].len() - count;
+                let mut crane: Vec<_> = stacks[src - 1].split_off(split);
+                stacks[dest - 1].append(&mut crane);
+            } else {
+                for _ in 0..count {
+                    let crane_contents = stacks[src - 1].pop().unwrap();
+                    stacks[dest - 1].push(crane_contents);
                 }
             }
         }
-        Containers { stacks: stacks }
+        Containers { stacks }
     }
+}
+
+impl Containers {
     fn tops(&self) -> String {
         self.stacks.iter().map(|v| v[v.len() - 1]).collect()
     }
 }
 
-impl Day for Day05 {
+impl DayParsed for Day05 {
+    type Parsed = Procedure;
+
     fn number(&self) -> u8 {
         5
     }
-    fn part01(&self) -> Result<()> {
-        let lines = self.input()?;
-        let container = Containers::read(lines.iter().map(|s| s.as_str()).collect(), false);
-        println!("{}", container.tops());
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<Procedure> {
+        Procedure::parse(input.iter().map(|s| s.as_str()).collect())
+    }
+    fn part01(&self, procedure: &Procedure) -> Result<Answer> {
+        Ok(procedure.run(false).tops().into())
     }
-    fn part02(&self) -> Result<()> {
-        let lines = self.input()?;
-        let container = Containers::read(lines.iter().map(|s| s.as_str()).collect(), true);
-        println!("{}", container.tops());
-        Ok(())
+    fn part02(&self, procedure: &Procedure) -> Result<Answer> {
+        Ok(procedure.run(true).tops().into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     fn test_vals() -> Vec<&'static str> {
         vec![
@@ -98,14 +163,49 @@ mod tests {
 
     #[test]
     fn test_p1() {
-        let c = Containers::read(test_vals(), false);
+        let c = Procedure::parse(test_vals()).unwrap().run(false);
         println!("{:?}", c.stacks);
         assert_eq!(c.tops(), "CMZ");
     }
     #[test]
     fn test_p2() {
-        let c = Containers::read(test_vals(), true);
+        let c = Procedure::parse(test_vals()).unwrap().run(true);
         println!("{:?}", c.stacks);
         assert_eq!(c.tops(), "MCD");
     }
+
+    #[test]
+    fn test_parse_error() {
+        let mut vals = test_vals();
+        vals[7] = "move 2 from 4 to 1";
+        let err = Procedure::parse(vals.clone()).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 8, column 13: expected a stack between 1 and 3, found `4 to 1`"
+        );
+        vals[7] = "move 2 from 2 to 1 twice";
+        assert!(Procedure::parse(vals).is_err());
+
+        let mut vals = test_vals();
+        vals[5] = "move 4 from 2 to 1";
+        let err = Procedure::parse(vals).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 6, column 6: expected at most 3 crates, found `4 from 2 to 1`"
+        );
+        let vals = vec!["[A]    ", " 1   2 ", "", "move 1 from 1 to 2"];
+        assert!(Procedure::parse(vals).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_never_panics(
+            lines in prop::collection::vec("\\PC{0,20}|( ?\\[[A-Z]\\] ?){0,4}|move [0-9]{1,3} from [0-9] to [0-9]|", 0..12)
+        ) {
+            if let Ok(procedure) = Procedure::parse(lines.iter().map(|s| s.as_str()).collect()) {
+                procedure.run(false).tops();
+                procedure.run(true).tops();
+            }
+        }
+    }
 }
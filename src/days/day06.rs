@@ -1,25 +1,30 @@
 use crate::interface::*;
+use anyhow::anyhow;
 use std::collections::{HashSet, VecDeque};
 
 pub struct D {}
 
-impl Day for D {
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = String;
+
     fn number(&self) -> u8 {
         6
     }
 
-    fn part01(&self) -> Result<()> {
-        let lines = self.input()?;
-        let s = lines.get(0).unwrap();
-        println!("{}", find_signal(&s[..], 4).unwrap());
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<String> {
+        input.first().cloned().ok_or_else(|| anyhow!("Empty input"))
+    }
+
+    fn part01(&self, s: &String) -> Result<Answer> {
+        let pos = find_signal(&s[..], 4).ok_or_else(|| anyhow!("No start-of-packet marker"))?;
+        Ok(pos.into())
     }
 
-    fn part02(&self) -> Result<()> {
-        let lines = self.input()?;
-        let s = lines.get(0).unwrap();
-        println!("{}", find_signal(&s[..], 14).unwrap());
-        Ok(())
+    fn part02(&self, s: &String) -> Result<Answer> {
+        let pos = find_signal(&s[..], 14).ok_or_else(|| anyhow!("No start-of-message marker"))?;
+        Ok(pos.into())
     }
 }
 
@@ -1,11 +1,15 @@
-use anyhow::anyhow;
-use lazy_static::lazy_static;
-use regex::Regex;
+use nom::{
+    branch::alt,
+    combinator::{map, opt, rest},
+    sequence::{pair, preceded, terminated},
+};
 use std::collections::HashMap;
 
 use crate::interface::*;
+use crate::parse::{expect, lit, number, parse_line, PResult};
+use anyhow::anyhow;
 
-struct Dirs {
+pub struct Dirs {
     dirs: HashMap<String, usize>,
 }
 
@@ -14,54 +18,70 @@ fn join(s1: &String, s2: &String) -> String {
     vec![s1.to_string(), s2.to_string()].join(sep)
 }
 
+/// One line of the terminal session.
+enum Line<'a> {
+    Cd(Option<&'a str>),
+    Ls,
+    Dir,
+    File(usize),
+}
+
+fn command(s: &str) -> PResult<'_, Line<'_>> {
+    let cd = map(preceded(lit("cd"), opt(preceded(lit(" "), rest))), Line::Cd);
+    let ls = map(lit("ls"), |_| Line::Ls);
+    preceded(lit("$ "), expect("`cd` or `ls`", alt((cd, ls))))(s)
+}
+
+fn listing(s: &str) -> PResult<'_, Line<'_>> {
+    let dir = map(lit("dir"), |_| Line::Dir);
+    let file = map(number("a file size"), Line::File);
+    terminated(alt((dir, file)), pair(lit(" "), rest))(s)
+}
+
+fn terminal_line(s: &str) -> PResult<'_, Line<'_>> {
+    expect("a command or a directory listing", alt((command, listing)))(s)
+}
+
 impl Dirs {
-    fn new(input: &Vec<String>) -> Result<Dirs> {
-        let mut last_cmd = String::new();
+    fn new(input: &[String]) -> Result<Dirs> {
+        let mut listing = false;
         let mut paths: Vec<String> = Vec::new();
         let mut map: HashMap<String, usize> = HashMap::new();
 
-        lazy_static! {
-            static ref CMD_RE: Regex = Regex::new(r"^\$ (?P<cmd>[a-z]+)( (?P<arg>.*))?$").unwrap();
-            static ref LS_RE: Regex =
-                Regex::new(r"^((?P<dir>dir)|(?P<size>[0-9]+)) (?P<name>.*)$").unwrap();
-            static ref EMPTY_S: String = String::new();
-        }
-        for s in input {
-            if let Some(cmd) = CMD_RE.captures(&s) {
-                last_cmd = cmd["cmd"].to_string();
-                if last_cmd == "cd" {
-                    let arg = &cmd["arg"];
-                    if arg == ".." {
-                        paths.pop();
-                    } else {
-                        if arg == "/" {
+        for (n, s) in input.iter().enumerate() {
+            let err = |expected: &str, offset: usize| ParseError::new(expected, s, offset).at_line(n + 1);
+            match parse_line(s, terminal_line).map_err(|e| e.at_line(n + 1))? {
+                Line::Cd(None) => Err(err("a directory", s.len()))?,
+                Line::Cd(Some(arg)) => {
+                    listing = false;
+                    let start = s.len() - arg.len();
+                    match arg {
+                        ".." => {
+                            paths.pop();
+                        }
+                        "/" => {
                             paths.clear();
                             paths.push("/".to_string());
-                        } else if arg.contains("/") {
-                            Err(anyhow!("Invalid {} arg '{}'", last_cmd, arg))?;
-                        } else {
-                            let path = join(paths.last().unwrap(), &arg.to_string());
+                        }
+                        name if name.contains("/") => Err(err("a directory name", start))?,
+                        name => {
+                            let parent = paths.last().ok_or_else(|| err("`/` before any other directory", start))?;
+                            let path = join(parent, &name.to_string());
                             paths.push(path);
                         }
                     }
                 }
-            } else if let Some(dir) = LS_RE.captures(&s) {
-                if last_cmd != "ls" {
-                    unimplemented!();
-                }
-                if dir.name("dir").is_some() {
-                    continue;
-                }
-                let size: usize = dir["size"].parse().unwrap();
-                for p in &paths[..] {
-                    if p != "" {
-                        let e = map.entry(p.to_string()).or_insert(0);
-                        *e += size;
+                Line::Ls => listing = true,
+                Line::Dir | Line::File(_) if !listing => Err(err("a command", 0))?,
+                Line::Dir => (),
+                Line::File(size) => {
+                    for p in &paths[..] {
+                        if p != "" {
+                            let e = map.entry(p.to_string()).or_insert(0);
+                            *e = e.checked_add(size).ok_or_else(|| err("a smaller file size", 0))?;
+                        }
                     }
                 }
-            } else {
-                eprintln!("Unknown input: {}", s);
-                unimplemented!();
             }
         }
 
@@ -70,11 +90,14 @@ impl Dirs {
     fn small_size(&self) -> usize {
         self.dirs.values().filter(|v| **v <= 100000).sum()
     }
-    fn find_min_delete(&self, target: usize) -> usize {
-        let total = 70000000;
-        let current = self.dirs["/"];
-        let required = target - (total - current);
-        self.dirs.values().fold(total, |a, e| {
+    fn find_min_delete(&self, target: usize) -> Result<usize> {
+        let total: usize = 70000000;
+        let current = *self.dirs.get("/").ok_or_else(|| anyhow!("No files listed under `/`"))?;
+        let free = total
+            .checked_sub(current)
+            .ok_or_else(|| anyhow!("{} used on a disk of {}", current, total))?;
+        let required = target.saturating_sub(free);
+        Ok(self.dirs.values().fold(total, |a, e| {
             let v = *e;
             if v < required {
                 // println!("Too small! {}", v);
@@ -86,34 +109,36 @@ impl Dirs {
                 // println!("Very nice! {}", v);
                 v
             }
-        })
+        }))
     }
 }
 
 pub struct D {}
 
-impl Day for D {
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = Dirs;
+
     fn number(&self) -> u8 {
         7
     }
 
-    fn part01(&self) -> Result<()> {
-        let dir = Dirs::new(&self.input()?)?;
-        println!("{}", dir.small_size());
-
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<Dirs> {
+        Dirs::new(input)
     }
-    fn part02(&self) -> Result<()> {
-        let dir = Dirs::new(&self.input()?)?;
-        println!("{}", dir.find_min_delete(30000000));
-
-        Ok(())
+    fn part01(&self, dir: &Dirs) -> Result<Answer> {
+        Ok(dir.small_size().into())
+    }
+    fn part02(&self, dir: &Dirs) -> Result<Answer> {
+        Ok(dir.find_min_delete(30000000)?.into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     fn test_vals() -> Vec<String> {
         r"$ cd /
@@ -154,6 +179,34 @@ $ ls
     fn test_p2() {
         let dir: Dirs = Dirs::new(&test_vals()).unwrap();
         assert_eq!(dir.dirs.len(), 4);
-        assert_eq!(dir.find_min_delete(30000000), 24933642);
+        assert_eq!(dir.find_min_delete(30000000).unwrap(), 24933642);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let mut vals = test_vals();
+        vals[6] = "$ cd a/e".to_string();
+        let err = Dirs::new(&vals).err().unwrap();
+        assert_eq!(err.to_string(), "line 7, column 6: expected a directory name, found `a/e`");
+
+        vals[6] = "$ mv a e".to_string();
+        assert!(Dirs::new(&vals).is_err());
+        let rootless = Dirs::new(&split_str("$ ls\n12 x")).unwrap();
+        assert!(rootless.find_min_delete(30000000).is_err());
+        let full = Dirs::new(&split_str("$ cd /\n$ ls\n70000001 x")).unwrap();
+        assert!(full.find_min_delete(30000000).is_err());
+        assert!(Dirs::new(&split_str("$ cd a")).is_err());
+        assert!(Dirs::new(&split_str("12 x")).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_never_panics(
+            lines in prop::collection::vec("\\PC{0,20}|\\$ cd [a-z/.]{0,3}|\\$ ls|dir [a-z]{1,3}|[0-9]{1,25} [a-z.]{1,5}", 0..20)
+        ) {
+            if let Ok(dirs) = Dirs::new(&lines) {
+                let _ = dirs.find_min_delete(30000000);
+            }
+        }
     }
 }
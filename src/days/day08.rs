@@ -1,51 +1,45 @@
+use crate::geometry::{Direction, Point};
+use crate::grid::Grid;
 use crate::interface::*;
 
-struct Grove {
-    w: usize,
-    h: usize,
-    ch: Vec<char>,
-}
+/// A tree's height, from 0 to 9.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Tree(u8);
 
-impl Grove {
-    fn new(input: &Vec<String>) -> Grove {
-        let w = input[0].len();
-        let h = input.len();
-        let ch: Vec<_> = input.join("").chars().collect();
+impl TryFrom<char> for Tree {
+    type Error = &'static str;
 
-        Grove { w, h, ch }
+    fn try_from(c: char) -> Result<Tree, &'static str> {
+        c.to_digit(10).map(|d| Tree(d as u8)).ok_or("a tree height")
     }
+}
 
-    fn idx(&self, x: usize, y: usize) -> usize {
-        y * self.w + x
-    }
+pub struct Grove {
+    trees: Grid<Tree>,
+}
 
-    fn sightlines(&self, x: usize, y: usize) -> Vec<Vec<usize>> {
-        let left: Vec<_> = (0..x).map(|i| self.idx(i, y)).collect();
-        let right: Vec<_> = ((x + 1)..self.w).map(|i| self.idx(i, y)).collect();
-        let top: Vec<_> = (0..y).map(|i| self.idx(x, i)).collect();
-        let bottom: Vec<_> = ((y + 1)..self.h).map(|i| self.idx(x, i)).collect();
+impl Grove {
+    fn new(input: &[String]) -> Result<Grove> {
+        Ok(Grove { trees: Grid::parse(input)? })
+    }
 
-        vec![left, right, top, bottom]
+    /// The trees seen looking from `(x, y)` in `dir`, nearest first.
+    fn sightline(&self, x: usize, y: usize, dir: Direction) -> impl Iterator<Item = Tree> + '_ {
+        let from = Point::try_from((x, y)).unwrap();
+        self.trees.ray(from, dir).copied()
     }
 
     fn visible(&self, x: usize, y: usize) -> bool {
-        if x == 0 || x == self.w - 1 || y == 0 || y == self.h - 1 {
-            return true;
-        }
-        let pos = y * self.w + x;
-        let val = self.ch[pos];
-        let hidden = self
-            .sightlines(x, y)
-            .iter()
-            .map(|l| l.iter().any(|c| self.ch[*c] >= val))
-            .all(|b| b);
-        return !hidden;
+        let val = self.trees[(x, y)];
+        Direction::ALL
+            .into_iter()
+            .any(|dir| self.sightline(x, y, dir).all(|tree| tree < val))
     }
 
     fn count_visible(&self) -> usize {
         let mut viz = 0;
-        for x in 0..self.w {
-            for y in 0..self.h {
+        for x in 0..self.trees.width() {
+            for y in 0..self.trees.height() {
                 if self.visible(x, y) {
                     viz += 1;
                 }
@@ -55,26 +49,25 @@ impl Grove {
     }
 
     fn scenic_score(&self, x: usize, y: usize) -> usize {
-        let house_val = self.ch[self.idx(x, y)];
-        let score_vec = |v: Vec<(usize, usize)>| -> usize {
-            v.iter()
-                .position(|(ix, iy)| self.ch[self.idx(*ix, *iy)] >= house_val)
-                .map(|n| n + 1)
-                .or(Some(v.len()))
-                .unwrap()
-        };
-        let left = score_vec((0..x).rev().map(|i| (i, y)).collect());
-        let right = score_vec(((x + 1)..self.w).map(|i| (i, y)).collect());
-        let above = score_vec((0..y).rev().map(|i| (x, i)).collect());
-        let below = score_vec(((y + 1)..self.h).map(|i| (x, i)).collect());
-        let score = left * right * above * below;
-
-        score
+        let house_val = self.trees[(x, y)];
+        Direction::ALL
+            .into_iter()
+            .map(|dir| {
+                let mut seen = 0;
+                for tree in self.sightline(x, y, dir) {
+                    seen += 1;
+                    if tree >= house_val {
+                        break;
+                    }
+                }
+                seen
+            })
+            .product()
     }
 
     fn best_scenic_score(&self) -> usize {
-        (0..self.w)
-            .flat_map(|x| (0..self.h).map(move |y| self.scenic_score(x, y)))
+        (0..self.trees.width())
+            .flat_map(|x| (0..self.trees.height()).map(move |y| self.scenic_score(x, y)))
             .max()
             .unwrap()
     }
@@ -82,19 +75,22 @@ impl Grove {
 
 pub struct D {}
 
-impl Day for D {
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = Grove;
+
     fn number(&self) -> u8 {
         8
     }
-    fn part01(&self) -> Result<()> {
-        let grove = Grove::new(&self.input()?);
-        println!("{}", grove.count_visible());
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<Grove> {
+        Grove::new(input)
     }
-    fn part02(&self) -> Result<()> {
-        let grove = Grove::new(&self.input()?);
-        println!("{}", grove.best_scenic_score());
-        Ok(())
+    fn part01(&self, grove: &Grove) -> Result<Answer> {
+        Ok(grove.count_visible().into())
+    }
+    fn part02(&self, grove: &Grove) -> Result<Answer> {
+        Ok(grove.best_scenic_score().into())
     }
 }
 
@@ -114,14 +110,24 @@ mod tests {
     }
     #[test]
     fn test_p1() {
-        let g = Grove::new(&test_vals());
+        let g = Grove::new(&test_vals()).unwrap();
         assert_eq!(g.count_visible(), 21);
     }
     #[test]
     fn test_p2() {
-        let g = Grove::new(&test_vals());
+        let g = Grove::new(&test_vals()).unwrap();
         assert_eq!(g.scenic_score(2, 1), 4);
         assert_eq!(g.scenic_score(2, 3), 8);
         assert_eq!(g.best_scenic_score(), 8);
     }
+    #[test]
+    fn test_parse_error() {
+        let mut vals = test_vals();
+        vals[1] = "255 2".to_string();
+        let err = Grove::new(&vals).err().unwrap();
+        assert_eq!(err.to_string(), "line 2, column 4: expected a tree height, found ` 2`");
+        vals[1] = "2551".to_string();
+        assert!(Grove::new(&vals).is_err());
+        assert!(Grove::new(&[]).is_err());
+    }
 }
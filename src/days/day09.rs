@@ -1,52 +1,69 @@
 use std::collections::HashSet;
 
-use adventools::prelude::*;
+use crate::frames;
+use crate::geometry::{Direction, Point};
+use crate::interface::*;
 
-type Pos = (i32, i32);
+/// A direction to move the head in, and how many steps to take.
+type Move = (Direction, usize);
 
-fn sign(i: i32) -> i32 {
-    if i == 0 {
-        0
-    } else if i < 0 {
-        -1
+/// Where a knot goes once the knot ahead of it has moved: nowhere while they
+/// still touch, otherwise one step straight or diagonally towards it.
+fn chase(head: Point, tail: Point) -> Point {
+    if head.chebyshev(tail) > 1 {
+        tail + (head - tail).signum()
     } else {
-        1
+        tail
     }
 }
 
-fn chase((hx, hy): Pos, (tx, ty): Pos) -> Pos {
-    let nx: i32 = hx - tx;
-    let ny: i32 = hy - ty;
-    if nx.abs() > 1 || ny.abs() > 1 {
-        (tx + sign(nx), ty + sign(ny))
-    } else {
-        (tx, ty)
+fn read_move(s: &str) -> Result<Move, ParseError> {
+    let dir = s
+        .chars()
+        .next()
+        .and_then(Direction::from_letter)
+        .ok_or_else(|| ParseError::new("`U`, `D`, `L` or `R`", s, 0))?;
+    if s.get(1..2) != Some(" ") {
+        return Err(ParseError::new("a space", s, 1));
     }
+    Ok((dir, parse_field(s, 2..s.len(), "a step count")?))
 }
 
-fn watch_tail(len: usize, moves: Vec<String>) -> usize {
-    let mut visited: HashSet<Pos> = HashSet::new();
-    let mut snake: Vec<Pos> = Vec::new();
-    for _ in 0..len {
-        snake.push((0, 0));
-    }
-    for m in moves {
-        let (dx, dy) = match m.chars().next().unwrap() {
-            'U' => (0, -1),
-            'D' => (0, 1),
-            'L' => (-1, 0),
-            'R' => (1, 0),
-            _ => unimplemented!(),
-        };
-        let count: usize = m[2..].parse().unwrap();
+fn read_moves(lines: &[String]) -> Result<Vec<Move>> {
+    Ok(parse_each(lines, read_move)?)
+}
+
+/// Draws the rope over the ground its tail has covered, with the head as
+/// `H`, the other knots numbered and the starting point as `s`.
+fn draw_rope(snake: &[Point], visited: &HashSet<Point>) -> Vec<String> {
+    let all = || snake.iter().chain(visited).chain([&Point::ORIGIN]);
+    let (min_x, max_x) = (all().map(|p| p.x).min().unwrap(), all().map(|p| p.x).max().unwrap());
+    let (min_y, max_y) = (all().map(|p| p.y).min().unwrap(), all().map(|p| p.y).max().unwrap());
+    let width = (max_x - min_x + 1) as usize;
+    let height = (max_y - min_y + 1) as usize;
+    frames::draw(width, height, |x, y| {
+        let pos = Point::new(x as i32 + min_x, y as i32 + min_y);
+        match snake.iter().position(|&knot| knot == pos) {
+            Some(0) => 'H',
+            Some(n) => char::from_digit(n as u32, 36).unwrap(),
+            None if pos == Point::ORIGIN => 's',
+            None if visited.contains(&pos) => '#',
+            None => '.',
+        }
+    })
+}
+
+fn watch_tail(len: usize, moves: &[Move]) -> usize {
+    let mut visited: HashSet<Point> = HashSet::new();
+    let mut snake = vec![Point::ORIGIN; len];
+    for &(dir, count) in moves {
         for _ in 0..count {
-            let (hx, hy) = snake[0];
-            snake[0] = (hx + dx, hy + dy);
+            snake[0] = snake[0].step(dir);
             for idx in 1..len {
-                let updated = chase(snake[idx - 1], snake[idx]);
-                snake[idx] = updated;
+                snake[idx] = chase(snake[idx - 1], snake[idx]);
             }
-            visited.insert(*snake.iter().rev().next().unwrap());
+            visited.insert(snake[len - 1]);
+            frames::emit(|| draw_rope(&snake, &visited));
         }
     }
     visited.len()
@@ -54,26 +71,33 @@ fn watch_tail(len: usize, moves: Vec<String>) -> usize {
 
 pub struct D {}
 
-impl Day for D {
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = Vec<Move>;
+
     fn number(&self) -> u8 {
         9
     }
-    fn part01(&self) -> Result<()> {
-        let moves = self.input()?;
-        println!("{}", watch_tail(2, moves));
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<Vec<Move>> {
+        read_moves(input)
+    }
+    fn part01(&self, moves: &Vec<Move>) -> Result<Answer> {
+        Ok(watch_tail(2, moves).into())
     }
-    fn part02(&self) -> Result<()> {
-        let moves = self.input()?;
-        println!("{}", watch_tail(10, moves));
-        Ok(())
+    fn part02(&self, moves: &Vec<Move>) -> Result<Answer> {
+        Ok(watch_tail(10, moves).into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    fn test_vals() -> Vec<String> {
+    fn test_vals() -> Vec<Move> {
+        read_moves(&test_lines()).unwrap()
+    }
+
+    fn test_lines() -> Vec<String> {
         r"R 4
 U 4
 L 3
@@ -87,7 +111,11 @@ R 2"
         .collect()
     }
 
-    fn bigger_test() -> Vec<String> {
+    fn bigger_test() -> Vec<Move> {
+        read_moves(&bigger_lines()).unwrap()
+    }
+
+    fn bigger_lines() -> Vec<String> {
         r"R 5
 U 8
 L 8
@@ -104,12 +132,31 @@ U 20"
     #[test]
     fn test_p1() {
         let t = test_vals();
-        assert_eq!(watch_tail(2, t), 13);
+        assert_eq!(watch_tail(2, &t), 13);
     }
     #[test]
     fn test_p2() {
         let t = test_vals();
-        assert_eq!(watch_tail(10, t), 1);
-        assert_eq!(watch_tail(10, bigger_test()), 36);
+        assert_eq!(watch_tail(10, &t), 1);
+        assert_eq!(watch_tail(10, &bigger_test()), 36);
+    }
+
+    #[test]
+    fn test_draw_rope() {
+        let snake = vec![Point::new(2, -1), Point::new(1, 0), Point::ORIGIN];
+        let visited: HashSet<Point> = [Point::ORIGIN, Point::new(-1, 0)].into_iter().collect();
+        assert_eq!(draw_rope(&snake, &visited), vec!["...H", "#21."]);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let mut lines = test_lines();
+        lines[3] = "D -1".to_string();
+        let err = read_moves(&lines).unwrap_err();
+        assert_eq!(err.to_string(), "line 4, column 3: expected a step count, found `-1`");
+        lines[3] = "X 1".to_string();
+        assert!(read_moves(&lines).is_err());
+        lines[3] = "D".to_string();
+        assert!(read_moves(&lines).is_err());
     }
 }
@@ -1,7 +1,23 @@
-use adventools::prelude::*;
+use crate::frames;
+use crate::interface::*;
 
-struct Machine {
-    instructions: Vec<String>,
+#[derive(Clone, Copy)]
+enum Instruction {
+    Noop,
+    Addx(i32),
+}
+
+fn read_instruction(s: &str) -> Result<Instruction, ParseError> {
+    match s.split_once(' ') {
+        None if s == "noop" => Ok(Instruction::Noop),
+        Some(("addx", _)) => Ok(Instruction::Addx(parse_field(s, 5..s.len(), "an integer")?)),
+        _ => Err(ParseError::new("`noop` or `addx <value>`", s, 0)),
+    }
+}
+
+#[derive(Clone)]
+pub struct Machine {
+    instructions: Vec<Instruction>,
     ip: usize,
     cycle: usize,
     ctr: usize,
@@ -9,7 +25,11 @@ struct Machine {
 }
 
 impl Machine {
-    fn new(inst: Vec<String>) -> Self {
+    fn parse(lines: &[String]) -> Result<Self> {
+        Ok(Machine::new(parse_each(lines, read_instruction)?))
+    }
+
+    fn new(inst: Vec<Instruction>) -> Self {
         Machine {
             instructions: inst,
             ip: 0,
@@ -19,25 +39,23 @@ impl Machine {
         }
     }
 
+    /// Runs one cycle, returning X during it. Once the program has run out,
+    /// X holds its last value.
     fn tick(&mut self) -> i32 {
         let cur = self.reg_x;
         self.ctr += 1;
         self.cycle += 1;
-        let mut op = self.instructions[self.ip].split(" ");
-        let inst = op.next().unwrap();
-
-        let completed = match inst {
-            "noop" => true,
-            "addx" => {
+        let completed = match self.instructions.get(self.ip) {
+            None => false,
+            Some(Instruction::Noop) => true,
+            Some(&Instruction::Addx(d)) => {
                 if self.ctr == 2 {
-                    let d: i32 = op.next().unwrap().parse().unwrap();
                     self.reg_x += d;
                     true
                 } else {
                     false
                 }
             }
-            _ => unimplemented!(),
         };
         if completed {
             self.ctr = 0;
@@ -70,6 +88,7 @@ impl Machine {
                 } else {
                     s.push('#');
                 }
+                frames::emit(|| crt_frame(&v, &s, w, h));
             }
             v.push(s);
         }
@@ -77,36 +96,51 @@ impl Machine {
     }
 }
 
+/// The CRT part way through drawing: finished rows, then the row being
+/// drawn, then blank rows.
+fn crt_frame(done: &[String], current: &str, w: usize, h: usize) -> Vec<String> {
+    let mut frame = done.to_vec();
+    frame.push(format!("{:<w$}", current, w = w));
+    frame.resize(h, " ".repeat(w));
+    frame
+}
+
 pub struct D {}
 
-impl Day for D {
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = Machine;
+
     fn number(&self) -> u8 {
         10
     }
-    fn part01(&self) -> Result<()> {
-        let prog = self.input()?;
-        let mut m = Machine::new(prog);
-        println!("{}", m.signal_readings(&vec![20, 60, 100, 140, 180, 220]));
-        Ok(())
-    }
-    fn part02(&self) -> Result<()> {
-        let prog = self.input()?;
-        let mut m = Machine::new(prog);
-        println!(
-            "\n{}",
-            m.draw(40, 6).join("\n").replace("#", "█").replace(".", " ")
-        );
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<Machine> {
+        Machine::parse(input)
+    }
+    fn part01(&self, machine: &Machine) -> Result<Answer> {
+        let mut m = machine.clone();
+        Ok(m.signal_readings(&vec![20, 60, 100, 140, 180, 220]).into())
+    }
+    fn part02(&self, machine: &Machine) -> Result<Answer> {
+        let mut m = machine.clone();
+        let picture: Vec<String> = m
+            .draw(40, 6)
+            .iter()
+            .map(|s| s.replace("#", "█").replace(".", " "))
+            .collect();
+        Ok(picture.into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures;
     use std::iter::zip;
 
     fn test_data() -> Vec<String> {
-        read_lines("test10.txt").unwrap()
+        fixtures::load(10).unwrap().remove(0).input
     }
 
     #[test]
@@ -116,7 +150,7 @@ mod tests {
 addx 3
 addx -5",
         );
-        let mut m = Machine::new(inst);
+        let mut m = Machine::parse(&inst).unwrap();
         m.tick();
         assert_eq!(1, m.reg_x);
         assert_eq!(1, m.cycle);
@@ -133,14 +167,29 @@ addx -5",
         assert_eq!(3, m.ip);
     }
 
+    #[test]
+    fn test_parse_error() {
+        let err = Machine::parse(&split_str("noop\naddx x3")).err().unwrap();
+        assert_eq!(err.to_string(), "line 2, column 6: expected an integer, found `x3`");
+        assert!(Machine::parse(&split_str("noop 1")).is_err());
+        assert!(Machine::parse(&split_str("addx")).is_err());
+    }
+
+    #[test]
+    fn test_short_program() {
+        let mut m = Machine::parse(&split_str("noop\naddx 2")).unwrap();
+        assert_eq!(m.signal_readings(&[20, 220]), 3 * 20 + 3 * 220);
+        assert_eq!(m.draw(4, 1), vec!["..##"]);
+    }
+
     #[test]
     fn test_p1() {
-        let mut m = Machine::new(test_data());
+        let mut m = Machine::parse(&test_data()).unwrap();
         assert_eq!(m.signal_readings(&vec![20, 60, 100, 140, 180, 220]), 13140);
     }
     #[test]
     fn test_p2() {
-        let mut m = Machine::new(test_data());
+        let mut m = Machine::parse(&test_data()).unwrap();
         let expected = split_str(
             r"##..##..##..##..##..##..##..##..##..##..
 ###...###...###...###...###...###...###.
@@ -1,10 +1,14 @@
-use adventools::prelude::*;
-use anyhow::anyhow;
-use lazy_static::lazy_static;
-use num::integer::lcm;
-use regex::{Captures, Regex};
+use crate::interface::*;
+use crate::parse::{blocks, comma_list, expect, failure, labelled, lit, number, parse_line, PResult};
+use nom::{
+    branch::alt,
+    combinator::map,
+    sequence::{delimited, preceded},
+};
+use num::integer::gcd;
 
 type ValueType = u128;
+#[derive(Clone)]
 enum Operation {
     Multiply(ValueType),
     Add(ValueType),
@@ -21,6 +25,7 @@ impl Operation {
     }
 }
 
+#[derive(Clone)]
 struct Monkey {
     id: usize,
     items: Vec<ValueType>,
@@ -28,46 +33,62 @@ struct Monkey {
     test_divisor: ValueType,
     dest_true: usize,
     dest_false: usize,
-    extra_divisor: ValueType,
     counter: usize,
 }
 
-fn parse<'a>(re: &Regex, input: &'a str) -> Result<Captures<'a>> {
-    let opt = re.captures(input);
-    opt.ok_or_else(|| anyhow!("Invalid input: '{}'", input))
+/// Worry levels, operands and the divisors' common multiple all stay below
+/// this, so that no operation can overflow.
+const MAX_WORRY: ValueType = 1 << 64;
+
+fn small<'a>(what: &'static str) -> impl FnMut(&'a str) -> PResult<'a, ValueType> {
+    move |s: &'a str| {
+        let (rest, n) = number(what)(s)?;
+        if n >= MAX_WORRY {
+            return Err(failure(s, "a value below 2^64"));
+        }
+        Ok((rest, n))
+    }
+}
+
+fn operation(s: &str) -> PResult<'_, Operation> {
+    let add = preceded(lit("+ "), map(small("a number"), Operation::Add));
+    let square = map(lit("old"), |_| Operation::Square);
+    let multiply = preceded(
+        lit("* "),
+        alt((square, map(small("a number or `old`"), Operation::Multiply))),
+    );
+    expect("`+` or `*`", alt((add, multiply)))(s)
+}
+
+fn divisor(s: &str) -> PResult<'_, ValueType> {
+    let (rest, d) = number("a divisor")(s)?;
+    if d == 0 {
+        return Err(failure(s, "a non-zero divisor"));
+    }
+    Ok((rest, d))
 }
 
 impl Monkey {
-    fn new(input: &[String], extra_divisor: ValueType) -> Result<Monkey> {
-        lazy_static! {
-            static ref LINE_1: Regex = Regex::new(r"Monkey (\d+):").unwrap();
-            static ref LINE_2: Regex = Regex::new(r"Starting items: (\d+(, \d+)*)$").unwrap();
-            static ref LINE_3: Regex =
-                Regex::new(r"Operation: new = old ([+*]) (old|(\d+))$").unwrap();
-            static ref LINE_4: Regex = Regex::new(r"Test: divisible by (\d+)$").unwrap();
-            static ref LINE_5: Regex = Regex::new(r"If true: throw to monkey (\d+)").unwrap();
-            static ref LINE_6: Regex = Regex::new(r"If false: throw to monkey (\d+)").unwrap();
+    /// Parses the six lines describing a monkey, the first of which is line
+    /// `first_line` of the input.
+    fn new(input: &[String], first_line: usize) -> Result<Monkey, ParseError> {
+        let lines: Vec<&str> = (0..6).map(|i| input.get(i).map_or("", |s| s.as_str())).collect();
+        let at = |i: usize| move |e: ParseError| e.at_line(first_line + i);
+        if let Some(extra) = input.get(6) {
+            return Err(ParseError::new("a blank line", extra, 0).at_line(first_line + 6));
         }
-        let capture1 = parse(&LINE_1, &input[0])?;
-        let id = capture1[1].parse()?;
-        let capture2 = parse(&LINE_2, &input[1])?;
-        let starting: Vec<_> = capture2[1]
-            .split(", ")
-            .map(|s| s.parse::<ValueType>())
-            .collect::<Result<_, _>>()?;
-        let cap3 = parse(&LINE_3, &input[2])?;
-        let op = if &cap3[1] == "+" {
-            Operation::Add(cap3[2].parse()?)
-        } else if &cap3[1] == "*" && &cap3[2] == "old" {
-            Operation::Square
-        } else if &cap3[1] == "*" {
-            Operation::Multiply(cap3[3].parse()?)
-        } else {
-            Err(anyhow!("Invalid input: '{}'", &input[2]))?
-        };
-        let test_divisor: ValueType = parse(&LINE_4, &input[3])?[1].parse()?;
-        let dest_true = parse(&LINE_5, &input[4])?[1].parse()?;
-        let dest_false = parse(&LINE_6, &input[5])?[1].parse()?;
+
+        let id = parse_line(lines[0], delimited(lit("Monkey "), number("a monkey id"), lit(":")))
+            .map_err(at(0))?;
+        let items = comma_list(small("a worry level"));
+        let starting = parse_line(lines[1], labelled("Starting items", items)).map_err(at(1))?;
+        let op = parse_line(lines[2], labelled("Operation", preceded(lit("new = old "), operation)))
+            .map_err(at(2))?;
+        let test = labelled("Test", preceded(lit("divisible by "), divisor));
+        let test_divisor = parse_line(lines[3], test).map_err(at(3))?;
+        let throw = |label| labelled(label, preceded(lit("throw to monkey "), number("a monkey id")));
+        let dest_true = parse_line(lines[4], throw("If true")).map_err(at(4))?;
+        let dest_false = parse_line(lines[5], throw("If false")).map_err(at(5))?;
 
         Ok(Monkey {
             id,
@@ -76,16 +97,16 @@ impl Monkey {
             test_divisor,
             dest_true,
             dest_false,
-            extra_divisor,
             counter: 0,
         })
     }
 
-    fn look(&mut self) -> (Vec<ValueType>, Vec<ValueType>) {
+    /// Inspects every held item, dividing each new worry level by `relief`.
+    fn look(&mut self, relief: ValueType) -> (Vec<ValueType>, Vec<ValueType>) {
         let outputs: Vec<_> = self
             .items
             .iter()
-            .map(|&val| self.operation.apply(val) / self.extra_divisor)
+            .map(|&val| self.operation.apply(val) / relief)
             .collect();
         self.counter += self.items.len();
         self.items.clear();
@@ -103,32 +124,55 @@ impl Monkey {
     }
 }
 
-struct Barrel {
+#[derive(Clone)]
+pub struct Barrel {
     monkeys: Vec<Monkey>,
     lcm: ValueType,
 }
 
 impl Barrel {
-    fn new(v: &Vec<String>, extra_divisor: ValueType) -> Result<Barrel> {
-        let r = v
-            .chunks(7)
-            .map(|c| Monkey::new(c, extra_divisor))
+    fn new(v: &[String]) -> Result<Barrel> {
+        let blocks: Vec<_> = blocks(v).collect();
+        let r = blocks
+            .iter()
+            .map(|&(first, lines)| Monkey::new(lines, first + 1))
             .collect::<Result<Vec<_>, _>>()?;
-        if !r.iter().enumerate().all(|(i, m)| m.id == i) {
-            Err(anyhow!("Can't deal with monkeys out of order"))?;
+        for (i, m) in r.iter().enumerate() {
+            let (first, lines) = blocks[i];
+            if m.id != i {
+                // monkeys must be listed in order
+                Err(ParseError::new(format!("monkey {}", i), &lines[0], 7).at_line(first + 1))?;
+            }
+            for (k, dest) in [(4, m.dest_true), (5, m.dest_false)] {
+                if dest >= r.len() {
+                    let line = &lines[k];
+                    let col = line.rfind(' ').map_or(0, |p| p + 1);
+                    let expected = format!("a monkey below {}", r.len());
+                    Err(ParseError::new(expected, line, col).at_line(first + k + 1))?;
+                }
+            }
+        }
+        let mut lcm: ValueType = 1;
+        for (i, m) in r.iter().enumerate() {
+            let (first, lines) = blocks[i];
+            let line = &lines[3];
+            lcm = (lcm / gcd(lcm, m.test_divisor))
+                .checked_mul(m.test_divisor)
+                .filter(|&l| l < MAX_WORRY)
+                .ok_or_else(|| {
+                    let col = line.rfind(' ').map_or(0, |p| p + 1);
+                    ParseError::new("divisors with a common multiple below 2^64", line, col)
+                        .at_line(first + 4)
+                })?;
         }
-        let lcm = r
-            .iter()
-            .map(|m| m.test_divisor)
-            .fold(1, |acc, el| lcm(acc, el));
         Ok(Barrel { monkeys: r, lcm })
     }
 
-    fn process(&mut self) {
+    fn process(&mut self, relief: ValueType) {
         for idx in 0..self.monkeys.len() {
             let ((mut trues, mut falses), idx_true, idx_false) = {
                 let m = &mut self.monkeys[idx];
-                (m.look(), m.dest_true, m.dest_false)
+                (m.look(relief), m.dest_true, m.dest_false)
             };
             trues.iter_mut().for_each(|v| *v %= self.lcm);
             falses.iter_mut().for_each(|v| *v %= self.lcm);
@@ -147,26 +191,31 @@ impl Barrel {
 
 pub struct D {}
 
-impl Day for D {
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = Barrel;
+
     fn number(&self) -> u8 {
         11
     }
-    fn part01(&self) -> Result<()> {
-        let mut barrel = Barrel::new(&self.input()?, 3)?;
+    fn parse(&self, input: &[String]) -> Result<Barrel> {
+        Barrel::new(input)
+    }
+    fn part01(&self, barrel: &Barrel) -> Result<Answer> {
+        let mut barrel = barrel.clone();
         for _ in 0..20 {
-            barrel.process();
+            barrel.process(3);
         }
-        println!("{}", barrel.business());
-        Ok(())
+        Ok(barrel.business().into())
     }
 
-    fn part02(&self) -> Result<()> {
-        let mut barrel = Barrel::new(&self.input()?, 3)?;
+    fn part02(&self, barrel: &Barrel) -> Result<Answer> {
+        let mut barrel = barrel.clone();
         for _ in 0..10000 {
-            barrel.process();
+            barrel.process(1);
         }
-        println!("{}", barrel.business());
-        Ok(())
+        Ok(barrel.business().into())
     }
 }
 
@@ -176,6 +225,7 @@ mod tests {
 
     use super::*;
     use adventools::prelude::split_str;
+    use proptest::prelude::*;
 
     fn test_data() -> Vec<String> {
         split_str(
@@ -210,14 +260,14 @@ Monkey 3:
     }
     #[test]
     fn test_p1() {
-        let mut barrel = Barrel::new(&test_data(), 3).unwrap();
-        barrel.process();
+        let mut barrel = Barrel::new(&test_data()).unwrap();
+        barrel.process(3);
         assert_eq!(barrel.monkeys[0].items.len(), 4);
         assert_eq!(barrel.monkeys[1].items.len(), 6);
         assert_eq!(barrel.monkeys[0].items, vec![20, 23, 27, 26]);
         // assert_eq!(barrel.monkeys.iter().map(|m| m.counter).collect::<Vec<_>>(), vec![2,4,3,6]);
         for _ in 0..19 {
-            barrel.process()
+            barrel.process(3)
         }
         assert_eq!(barrel.monkeys[0].counter, 101);
         assert_eq!(barrel.monkeys[3].counter, 105);
@@ -225,14 +275,55 @@ Monkey 3:
     }
 
     #[test]
-    #[ignore = "broken"]
+    fn test_parse_error() {
+        let mut data = test_data();
+        data[16] = "  Operation: new = old - old".to_string();
+        let err = Barrel::new(&data).err().unwrap();
+        assert_eq!(
+            err.downcast_ref::<ParseError>().map(|e| (e.line, e.column)),
+            Some((Some(17), 24))
+        );
+
+        let mut data = test_data();
+        data[12] = "    If false: throw to monkey 7".to_string();
+        let err = Barrel::new(&data).err().unwrap();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(err.line, Some(13));
+        assert_eq!(err.expected, "a monkey below 4");
+        assert_eq!(err.found, "`7`");
+
+        assert!(Barrel::new(&test_data()[..10]).is_err());
+
+        let mut data = test_data();
+        for n in 0..4 {
+            data[n * 7 + 3] = format!("  Test: divisible by {}", (1u128 << 63) + 1 + n as u128 * 2);
+        }
+        let err = Barrel::new(&data).err().unwrap();
+        assert_eq!(err.downcast_ref::<ParseError>().unwrap().line, Some(11));
+
+        let mut data = test_data();
+        data[1] = format!("  Starting items: 79, {}", 1u128 << 64);
+        let err = Barrel::new(&data).err().unwrap();
+        assert_eq!(err.to_string(), "line 2, column 23: expected a value below 2^64, found `18446744073709551616`");
+        let squarer = split_str(
+            "Monkey 0:
+  Starting items: 79
+  Operation: new = old * old
+  Test: divisible by 18446744073709551629
+    If true: throw to monkey 0
+    If false: throw to monkey 0",
+        );
+        assert!(Barrel::new(&squarer).is_err());
+    }
+
+    #[test]
     fn test_p2() {
-        let mut barrel = Barrel::new(&test_data(), 3).unwrap();
+        let mut barrel = Barrel::new(&test_data()).unwrap();
         let mut expected: HashMap<usize, Vec<usize>> = HashMap::new();
         expected.insert(1, vec![2,4,3,6]);
         expected.insert(20, vec![99, 97, 8, 103]);
         for idx in 1..=10000 {
-            barrel.process();
+            barrel.process(1);
             if expected.contains_key(&idx) {
                 let exp = &expected[&idx];
                 let actual: Vec<_> = barrel.monkeys.iter().map(|m| m.counter).collect();
@@ -241,4 +332,14 @@ Monkey 3:
         }
         assert_eq!(barrel.business(), 2713310158);
     }
+
+    proptest! {
+        #[test]
+        fn test_parse_never_panics(lines in prop::collection::vec(
+            "\\PC{0,20}|Monkey [0-9]:|  Starting items: [0-9, ]{0,10}|  Operation: new = old [+*-] (old|[0-9]{1,3})|  Test: divisible by [0-9]{1,40}|    If (true|false): throw to monkey [0-9]|",
+            0..16,
+        )) {
+            let _ = Barrel::new(&lines);
+        }
+    }
 }
@@ -1,79 +1,93 @@
-use adventools::prelude::*;
+use crate::frames;
+use crate::geometry::Point;
+use crate::grid::Grid;
+use crate::interface::*;
+use crate::search::Bfs;
+use anyhow::anyhow;
 
 pub struct D {}
 
-impl Day for D {
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = Grid<Square>;
+
     fn number(&self) -> u8 {
         12
     }
-    fn part01(&self) -> Result<()> {
-        println!("{}", path(&self.input()?, "S"));
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<Grid<Square>> {
+        read_grid(input)
+    }
+    fn part01(&self, grid: &Grid<Square>) -> Result<Answer> {
+        Ok(path(grid, "S").ok_or_else(|| anyhow!("No way up to E"))?.into())
     }
 
-    fn part02(&self) -> Result<()> {
-        println!("{}", path(&self.input()?, "Sa"));
-        Ok(())
+    fn part02(&self, grid: &Grid<Square>) -> Result<Answer> {
+        Ok(path(grid, "Sa").ok_or_else(|| anyhow!("No way up to E"))?.into())
+    }
+}
+
+/// A square of the height map: a height from `a` to `z`, or the `S` or `E`
+/// marking the start and the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Square(char);
+
+impl TryFrom<char> for Square {
+    type Error = &'static str;
+
+    fn try_from(c: char) -> Result<Square, &'static str> {
+        match c {
+            'a'..='z' | 'S' | 'E' => Ok(Square(c)),
+            _ => Err("a height from `a` to `z`, `S` or `E`"),
+        }
     }
 }
 
-fn height(ch: char) -> i32 {
-    match ch {
-        'S' => 1,
-        'E' => 26,
-        'a'..='z' => ('a'..='z').position(|c| c == ch).unwrap() as i32,
-        _ => unimplemented!(),
+impl Square {
+    fn height(self) -> i32 {
+        match self.0 {
+            'S' => 1,
+            'E' => 26,
+            ch => ('a'..='z').position(|c| c == ch).unwrap() as i32,
+        }
     }
 }
 
-fn path(grid: &Vec<String>, starts: &str) -> usize {
-    let w = grid[0].len();
-    let h = grid.len();
-    let idx = |x: usize, y: usize| y * w + x;
-    let mut queue: Vec<(usize, usize)> = Vec::new();
-    let mut visited = vec![false; w * h];
-    for y in 0..h {
-        for (x, ch) in grid[y].char_indices() {
-            if starts.contains(ch) {
-                queue.push((x, y));
-                visited[idx(x, y)] = true;
-            }
+fn read_grid(input: &[String]) -> Result<Grid<Square>> {
+    let grid = Grid::<Square>::parse(input)?;
+    for mark in ['S', 'E'] {
+        if grid.iter().filter(|(_, sq)| sq.0 == mark).count() != 1 {
+            Err(anyhow!("Expected exactly one `{}` in the height map", mark))?;
         }
     }
-    for n in 0..(w * h) {
-        let mut next_queue: Vec<(usize, usize)> = Vec::new();
-        // println!("on distance {} queue size {}", n, queue.len());
-        while let Some((x, y)) = queue.pop() {
-            let ch = grid[y].chars().nth(x).unwrap();
-            if ch == 'E' {
-                return n;
-            }
-            let pos = height(ch);
-            for (dx, dy) in [(1, 0), (0, 1), (-1, 0), (0, -1)] {
-                if (x as i32 + dx) < 0 || (y as i32 + dy) < 0 {
-                    continue;
-                }
-                let nx = (x as i32 + dx) as usize;
-                let ny = (y as i32 + dy) as usize;
-                if nx == w || ny == h {
-                    continue;
-                }
-                if visited[idx(nx, ny)] {
-                    continue;
-                }
-                let npos = height(grid[ny].chars().nth(nx).unwrap());
-                if pos + 1 >= npos {
-                    visited[idx(nx, ny)] = true;
-                    next_queue.push((nx, ny));
-                }
-            }
+    Ok(grid)
+}
+
+/// The height map with the squares reached so far in upper case.
+fn draw_search(grid: &Grid<Square>, visited: impl Fn(Point) -> bool) -> Vec<String> {
+    frames::draw(grid.width(), grid.height(), |x, y| match grid[(x, y)].0 {
+        'S' | 'E' => '@',
+        c if visited(Point::new(x as i32, y as i32)) => c.to_ascii_uppercase(),
+        c => c,
+    })
+}
+
+/// The fewest steps from any square marked with one of `starts` to `E`.
+fn path(grid: &Grid<Square>, starts: &str) -> Option<usize> {
+    let sources = grid.iter().filter(|(_, sq)| starts.contains(sq.0)).map(|(p, _)| p);
+    let mut search = Bfs::new(sources, |&p: &Point| {
+        let limit = grid[p].height() + 1;
+        grid.neighbors4(p).filter(move |&n| grid[n].height() <= limit)
+    });
+    loop {
+        frames::emit(|| draw_search(grid, |p| search.is_visited(&p)));
+        if search.layer().iter().any(|&p| grid[p] == Square('E')) {
+            return Some(search.depth());
         }
-        if next_queue.is_empty() {
-            unimplemented!();
+        if !search.advance() {
+            return None;
         }
-        queue.append(&mut next_queue);
     }
-    0
 }
 
 #[cfg(test)]
@@ -91,11 +105,42 @@ abdefghi",
 
     #[test]
     fn test_p1() {
-        assert_eq!(path(&test_data(), "S"), 31);
+        assert_eq!(path(&read_grid(&test_data()).unwrap(), "S"), Some(31));
     }
 
     #[test]
     fn test_p2() {
-        assert_eq!(path(&test_data(), "Sa"), 29);
+        assert_eq!(path(&read_grid(&test_data()).unwrap(), "Sa"), Some(29));
+    }
+
+    #[test]
+    fn test_draw_search() {
+        let grid = Grid::parse(&test_data()[..2]).unwrap();
+        let visited = |p: Point| (p.y == 0 && p.x < 3) || p == Point::new(0, 1);
+        assert_eq!(draw_search(&grid, visited), vec!["@ABqponm", "Abcryxxl"]);
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let grid = read_grid(&split_str("Sbz\nzzE")).unwrap();
+        assert_eq!(path(&grid, "S"), None);
+    }
+
+    #[test]
+    fn test_read_grid() {
+        assert_eq!(read_grid(&test_data()).unwrap().render(|sq| sq.0), test_data());
+
+        let mut data = test_data();
+        data[2] = "accszExk1".to_string();
+        let err = read_grid(&data).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 9: expected a height from `a` to `z`, `S` or `E`, found `1`"
+        );
+        data[2] = "accszEx".to_string();
+        let err = read_grid(&data).unwrap_err();
+        assert_eq!(err.to_string(), "line 3, column 8: expected a row of width 8, found end of line");
+        data[2] = "accszxxk".to_string();
+        assert!(read_grid(&data).is_err());
     }
 }
@@ -1,4 +1,4 @@
-use adventools::prelude::*;
+use crate::interface::*;
 use anyhow::anyhow;
 use nom::{
     bytes::complete::tag,
@@ -8,10 +8,10 @@ use nom::{
     sequence::{delimited, terminated},
     IResult,
 };
-use std::{cmp::Ordering, str::FromStr};
+use std::{cmp::Ordering, fmt, str::FromStr};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-enum Packet {
+pub enum Packet {
     Number(usize),
     List(Vec<Packet>),
 }
@@ -55,6 +55,28 @@ impl Ord for Packet {
     }
 }
 
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Packet::Number(n) => write!(f, "{}", n),
+            Packet::List(packets) => {
+                write!(f, "[")?;
+                for (i, packet) in packets.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", packet)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// Deeper lists than this are refused rather than risk overflowing the
+/// stack in the recursive parser.
+const MAX_DEPTH: usize = 100;
+
 fn p_empty_list(s: &str) -> IResult<&str, Packet> {
     map_res(tag("[]"), |_| Ok::<_, anyhow::Error>(Packet::List(vec![])))(s)
 }
@@ -74,36 +96,56 @@ fn p_number(s: &str) -> IResult<&str, Packet> {
 }
 
 impl FromStr for Packet {
-    type Err = anyhow::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (leftover, p) = p_packet(s).unwrap();
-        if !leftover.is_empty() {
-            Err(anyhow!("Trailing data: {}", leftover))
-        } else {
-            Ok(p)
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let mut depth: usize = 0;
+        for (i, c) in s.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            if depth > MAX_DEPTH {
+                let expected = format!("at most {} nested lists", MAX_DEPTH);
+                return Err(ParseError::new(expected, s, i));
+            }
+        }
+        match p_packet(s) {
+            Ok(("", p)) => Ok(p),
+            Ok((leftover, _)) => Err(ParseError::new("end of line", s, s.len() - leftover.len())),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                Err(ParseError::new("a packet", s, s.len() - e.input.len()))
+            }
+            Err(nom::Err::Incomplete(_)) => Err(ParseError::new("a packet", s, s.len())),
         }
     }
 }
 
-fn ordered_indices(v: &Vec<String>) -> usize {
+fn parse_packets(v: &[String]) -> Result<Vec<Packet>> {
+    let mut packets = vec![];
+    for (n, s) in v.iter().enumerate() {
+        if !s.is_empty() {
+            packets.push(s.parse::<Packet>().map_err(|e| e.at_line(n + 1))?);
+        }
+    }
+    if packets.len() % 2 != 0 {
+        Err(anyhow!("Packets must come in pairs, found {}", packets.len()))?;
+    }
+    Ok(packets)
+}
+
+fn ordered_indices(packets: &[Packet]) -> usize {
     let mut sum = 0;
-    for (i, lines) in v.chunks(3).enumerate() {
-        let first: Packet = lines[0].parse().unwrap();
-        let second: Packet = lines[1].parse().unwrap();
-        if first < second {
+    for (i, pair) in packets.chunks(2).enumerate() {
+        if pair[0] < pair[1] {
             sum += i + 1;
         }
     }
     sum
 }
 
-fn decoder_key(v: &Vec<String>) -> usize {
-    let mut packets: Vec<_> = v
-        .iter()
-        .filter(|s| !s.is_empty())
-        .map(|s| s.parse::<Packet>())
-        .collect::<Result<_>>()
-        .unwrap();
+fn decoder_key(packets: &[Packet]) -> usize {
+    let mut packets = packets.to_vec();
     let div1: Packet = "[[2]]".parse().unwrap();
     let div2: Packet = "[[6]]".parse().unwrap();
     packets.push(div1.clone());
@@ -116,24 +158,29 @@ fn decoder_key(v: &Vec<String>) -> usize {
 
 pub struct D {}
 
-impl Day for D {
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = Vec<Packet>;
+
     fn number(&self) -> u8 {
         13
     }
-    fn part01(&self) -> Result<()> {
-
-        println!("{}", ordered_indices(&self.input()?));
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<Vec<Packet>> {
+        parse_packets(input)
+    }
+    fn part01(&self, packets: &Vec<Packet>) -> Result<Answer> {
+        Ok(ordered_indices(packets).into())
     }
-    fn part02(&self) -> Result<()> {
-        println!("{}", decoder_key(&self.input()?));
-        Ok(())
+    fn part02(&self, packets: &Vec<Packet>) -> Result<Answer> {
+        Ok(decoder_key(packets).into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_parse() {
@@ -213,10 +260,51 @@ mod tests {
     }
     #[test]
     fn test_p1() {
-        assert_eq!(ordered_indices(&test_data()), 13);
+        let packets = parse_packets(&test_data()).unwrap();
+        assert_eq!(ordered_indices(&packets), 13);
     }
     #[test]
     fn test_p2() {
-        assert_eq!(decoder_key(&test_data()), 140);
+        let packets = parse_packets(&test_data()).unwrap();
+        assert_eq!(decoder_key(&packets), 140);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = "[1,2]x".parse::<Packet>().unwrap_err();
+        assert_eq!(err.to_string(), "column 6: expected end of line, found `x`");
+
+        let mut data = test_data();
+        data[3] = "[[1],[2,3,4]".to_string();
+        let err = parse_packets(&data).unwrap_err();
+        assert_eq!(err.downcast_ref::<ParseError>().unwrap().line, Some(4));
+        assert!(parse_packets(&data[..1]).is_err());
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        let err = deep.parse::<Packet>().unwrap_err();
+        assert_eq!(err.column, MAX_DEPTH + 1);
+        let ok = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        assert!(ok.parse::<Packet>().is_ok());
+    }
+
+    fn packets() -> impl Strategy<Value = Packet> {
+        any::<usize>().prop_map(Packet::Number).prop_recursive(8, 64, 6, |inner| {
+            prop::collection::vec(inner, 0..6).prop_map(Packet::List)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_never_panics(s in "\\PC*|[\\[\\],0-9 ]{0,40}") {
+            let _ = s.parse::<Packet>();
+        }
+
+        #[test]
+        fn test_display_round_trip(packet in packets()) {
+            prop_assert_eq!(packet.to_string().parse::<Packet>().unwrap(), packet);
+        }
     }
 }
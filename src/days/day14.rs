@@ -1,47 +1,81 @@
 use std::convert::identity;
-use std::iter::repeat;
 
-use adventools::prelude::*;
-use adventools::grid::{Grid, CharSrc};
-
-use regex::Regex;
-use lazy_static::lazy_static;
+use crate::frames;
+use crate::geometry::Point;
+use crate::grid::Grid;
+use crate::interface::*;
+use crate::parse::{expect, failure, lit, number, parse_line, separated, PResult};
+use anyhow::anyhow;
 
 pub struct D {}
 
-impl Day for D {
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = Grid<Cell>;
+
     fn number(&self) -> u8 {
         14
     }
-    fn part01(&self) -> Result<()> {
-        let mut grid = grid_from_lines(&self.input()?);
-        println!("{}", count_drops(&mut grid));
-        Ok(())
+    fn parse(&self, input: &[String]) -> Result<Grid<Cell>> {
+        grid_from_lines(input)
     }
-    fn part02(&self) -> Result<()> {
-        let mut grid = grid_from_lines(&self.input()?);
+    fn part01(&self, grid: &Grid<Cell>) -> Result<Answer> {
+        let mut grid = grid.clone();
+        Ok(count_drops(&mut grid).into())
+    }
+    fn part02(&self, grid: &Grid<Cell>) -> Result<Answer> {
+        let mut grid = grid.clone();
         draw_floor(&mut grid);
-        println!("{}", count_drops(&mut grid));
-        Ok(())
+        Ok(count_drops(&mut grid).into())
     }
 }
-fn pairs_from_line(line: &String) -> Vec<(usize, usize)> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"\b(\d+),(\d+)\b").unwrap();
+
+/// Coordinates are kept below this so that the cave fits in memory.
+const MAX_COORD: usize = 10_000;
+/// Rock lies above this depth, so that the floor, and all the sand heaped on
+/// it, fits between the source and the left edge of the cave.
+const MAX_DEPTH: i32 = 497;
+
+fn coordinate(s: &str) -> PResult<'_, Point> {
+    let (rest, x) = number("an x coordinate")(s)?;
+    let (y_at, _) = lit(",")(rest)?;
+    let (rest, y) = number("a y coordinate")(y_at)?;
+    let p = Point::new(x, y);
+    if p.within(MAX_COORD, MAX_COORD).is_none() {
+        return Err(failure(s, format!("coordinates from 0 to {}", MAX_COORD - 1)));
     }
-    RE.captures_iter(line).map(|cap| (cap[1].parse().unwrap(), cap[2].parse().unwrap())).collect()
+    if p.y >= MAX_DEPTH {
+        return Err(failure(y_at, format!("a depth below {}", MAX_DEPTH)));
+    }
+    Ok((rest, p))
+}
+
+/// Reads a rock path such as `498,4 -> 498,6 -> 496,6`.
+fn pairs_from_line(line: &str) -> Result<Vec<Point>, ParseError> {
+    let point = |s| expect("`<x>,<y>`", coordinate)(s);
+    let mut last: Option<Point> = None;
+    let path = separated(" -> ", |s| {
+        let (rest, p) = point(s)?;
+        if last.is_some_and(|l| l.x != p.x && l.y != p.y) {
+            return Err(failure(s, "a point in line with the previous one"));
+        }
+        last = Some(p);
+        Ok((rest, p))
+    });
+    parse_line(line, path)
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
-enum Cell {
+pub enum Cell {
     Air,
     Rock,
     Sand,
     Source
 }
 
-impl CharSrc for Cell {
-    fn char(&self) -> char {
+impl Cell {
+    fn char(self) -> char {
         match self {
             Cell::Air => '.',
             Cell::Rock => '#',
@@ -51,37 +85,28 @@ impl CharSrc for Cell {
     }
 }
 
-fn grid_from_lines(input: &Vec<String>) -> Grid<Cell> {
-    let lines: Vec<_> = input.iter().map(pairs_from_line).collect();
+fn grid_from_lines(input: &[String]) -> Result<Grid<Cell>> {
+    let lines = parse_each(input, pairs_from_line)?;
     let all_pairs: Vec<_> = lines.iter().flat_map(identity).collect();
-    let max_y = *all_pairs.iter().map(|(_x, y)| y).max().unwrap() + 2;
-    let max_x = *all_pairs.iter().map(|(x, _y)| x).max().unwrap().max(&(max_y + 501));
-    // println!("max ({}, {})", max_x, max_y);
+    let max_y = all_pairs.iter().map(|p| p.y).max().ok_or_else(|| anyhow!("No rock paths"))?;
+    let max_y = max_y as usize + 2;
+    let max_x = (all_pairs.iter().map(|p| p.x).max().unwrap() as usize).max(max_y + 501);
     let mut grid = Grid::new(max_x+1, max_y+1, Cell::Air);
     grid[(500, 0)] = Cell::Source;
     for line in lines {
-        let first_iter = line.iter();
-        let second_iter = line.iter().skip(1);
-        for (&(x1, y1), &(x2, y2)) in first_iter.zip(second_iter).into_iter() {
-            let cells: Vec<_> = if x1 == x2 {
-                if y1 < y2 {
-                    repeat(x1).zip(y1..=y2).into_iter().collect()
-                } else {
-                    repeat(x1).zip(y2..=y1).into_iter().collect()
-                }
-            } else {
-                if x1 < x2 {
-                    (x1..=x2).zip(repeat(y1)).collect()
-                } else {
-                    (x2..=x1).zip(repeat(y1)).collect()
+        for pair in line.windows(2) {
+            let (mut p, end) = (pair[0], pair[1]);
+            let step = (end - p).signum();
+            loop {
+                grid[p] = Cell::Rock;
+                if p == end {
+                    break;
                 }
-            };
-            for xy in cells {
-                grid[xy] =Cell::Rock;
+                p += step;
             }
         }
     }
-    grid
+    Ok(grid)
 }
 
 fn draw_floor(grid: &mut Grid<Cell>) {
@@ -91,41 +116,43 @@ fn draw_floor(grid: &mut Grid<Cell>) {
     }
 }
 
-fn drop_sand(grid: &mut Grid<Cell>) -> Option<(usize, usize)> {
-    let mut x = 500;
-    let mut y = 0;
-    loop {
-        if x >= grid.width() || y >= grid.height() || x == 0 || grid[(x,y)] == Cell::Sand {
-            return None
-        }
-        for (dx, dy) in [(0, 1), (-1, 1), (1, 1), (0, 0)] {
-            if dx == 0 && dy == 0 {
-                // eagle has landed
-                grid[(x,y)] = Cell::Sand;
-                return Some((x,y));
-            }
-            let nx = ((x as i32) + dx) as usize;
-            let ny = y + dy;
-            if nx == grid.width() || ny == grid.height() || nx == 0 {
-                // falling off the edge
-                return None;
-            }
-            if grid[(nx, ny)] == Cell::Air {
-                x = nx;
-                y = ny;
-                break;
+/// Where sand tries to go next, in order of preference.
+const FALLS: [Point; 3] = [Point::new(0, 1), Point::new(-1, 1), Point::new(1, 1)];
+
+fn drop_sand(grid: &mut Grid<Cell>) -> Option<Point> {
+    let mut p = Point::new(500, 0);
+    if *grid.get(p)? == Cell::Sand {
+        return None;
+    }
+    'falling: loop {
+        for fall in FALLS {
+            // falling off the edge
+            let next = Some(p + fall).filter(|n| n.x != 0)?;
+            if *grid.get(next)? == Cell::Air {
+                p = next;
+                continue 'falling;
             }
         }
+        // eagle has landed
+        grid[p] = Cell::Sand;
+        return Some(p);
     }
 }
 
+/// The part of the cave that has anything in it.
+fn draw_cave(grid: &Grid<Cell>) -> Vec<String> {
+    let used = |x: usize| grid.column(x).any(|&cell| cell != Cell::Air);
+    let min_x = (0..grid.width()).find(|&x| used(x)).unwrap_or(0);
+    let max_x = (0..grid.width()).rev().find(|&x| used(x)).unwrap_or(0);
+    grid.view(min_x..max_x + 1, 0..grid.height()).render(|cell| cell.char())
+}
+
 fn count_drops(grid: &mut Grid<Cell>) -> usize {
     for i in (0 as usize).. {
         if drop_sand(grid) == None {
             return i;
-        // } else {
-        //     println!("iter {}\n{}", i, grid);
         }
+        frames::emit(|| draw_cave(grid));
     }
     unreachable!()
 }
@@ -140,13 +167,37 @@ mod tests {
     }
     #[test]
     fn test_p1() {
-        let mut grid = grid_from_lines(&test_data());
+        let mut grid = grid_from_lines(&test_data()).unwrap();
         assert_eq!(count_drops(&mut grid), 24);
     }
     #[test]
     fn test_p2() {
-        let mut grid = grid_from_lines(&test_data());
+        let mut grid = grid_from_lines(&test_data()).unwrap();
         draw_floor(&mut grid);
         assert_eq!(count_drops(&mut grid), 93);
     }
+    #[test]
+    fn test_draw_cave() {
+        let mut grid = grid_from_lines(&test_data()).unwrap();
+        drop_sand(&mut grid);
+        let cave = draw_cave(&grid);
+        assert_eq!(cave[0], "......+...");
+        assert_eq!(cave[8], "......o.#.");
+        assert_eq!(cave[9], "#########.");
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = grid_from_lines(&split_str("498,4 -> 498,6\n503,4 -> 502;4")).err().unwrap();
+        assert_eq!(err.to_string(), "line 2, column 13: expected `,`, found `;4`");
+        let err = pairs_from_line("498,4 -> x").unwrap_err();
+        assert_eq!(err.to_string(), "column 10: expected `<x>,<y>`, found `x`");
+        let err = pairs_from_line("498,4 -> 496,6").unwrap_err();
+        assert_eq!(err.column, 10);
+        assert!(pairs_from_line("498,4 -> 498,").is_err());
+        assert!(grid_from_lines(&[]).is_err());
+        assert!(pairs_from_line("498,4 -> 498,99999999999").is_err());
+        let err = pairs_from_line("498,4 -> 498,600").unwrap_err();
+        assert_eq!(err.to_string(), "column 14: expected a depth below 497, found `600`");
+    }
 }
@@ -1,111 +1,124 @@
-use std::{str::FromStr, ops::RangeInclusive, collections::HashSet};
+use std::{str::FromStr, ops::RangeInclusive, fmt, time::Duration};
 
-use adventools::prelude::*;
+use crate::geometry::Point;
+use crate::interface::*;
+use crate::intervals::IntervalSet;
+use crate::parse::{failure, key_value, lit, number, parse_line, PResult};
 use anyhow::anyhow;
-use lazy_static::lazy_static;
-use regex::Regex;
+use nom::sequence::preceded;
 
 pub struct D {}
 
-impl DayParsed for D {}
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = Vec<SensorBeaconPair>;
 
-impl Day for D {
     fn number(&self) -> u8 {
         15
     }
-    fn part01(&self) -> Result<()> {
-        let sensors = self.input_as::<SensorBeaconPair>()?;
-        println!("{}", total_occluded(2000000, &sensors));
-        Ok(())
+    fn slow(&self) -> bool {
+        // part 2 scans all 4,000,000 rows for the gap
+        true
+    }
+    fn budget(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+    fn parse(&self, input: &[String]) -> Result<Self::Parsed> {
+        parse_lines(input)
     }
-    fn part02(&self) -> Result<()> {
-        let sensors = self.input_as::<SensorBeaconPair>()?;
-        let signal = find_missing_beacon(4000000, 4000000, &sensors).unwrap();
-        println!("{}", signal);
-        Ok(())
+    fn part01(&self, sensors: &Self::Parsed) -> Result<Answer> {
+        Ok(total_occluded(2000000, sensors).into())
+    }
+    fn part02(&self, sensors: &Self::Parsed) -> Result<Answer> {
+        let signal = find_missing_beacon(4000000, 4000000, sensors)?
+            .ok_or_else(|| anyhow!("No gap for the distress beacon"))?;
+        Ok(signal.into())
     }
 }
 
-struct SensorBeaconPair {
-    sx: i32,
-    sy: i32,
-    bx: i32,
-    by: i32,
+#[derive(Debug, PartialEq, Eq)]
+pub struct SensorBeaconPair {
+    sensor: Point,
+    beacon: Point,
     coverage: i32,
 }
 
+fn position(s: &str) -> PResult<'_, Point> {
+    let (s, x) = key_value("x", number("a coordinate"))(s)?;
+    let (s, _) = lit(", ")(s)?;
+    let (s, y) = key_value("y", number("a coordinate"))(s)?;
+    Ok((s, Point::new(x, y)))
+}
+
+fn report(s: &str) -> PResult<'_, SensorBeaconPair> {
+    let (s, sensor) = preceded(lit("Sensor at "), position)(s)?;
+    let (at_beacon, _) = lit(": closest beacon is at ")(s)?;
+    let (s, beacon) = position(at_beacon)?;
+    let coverage = i32::try_from(sensor.manhattan(beacon))
+        .map_err(|_| failure(&at_beacon["x=".len()..], "a beacon less than 2^31 away"))?;
+    Ok((s, SensorBeaconPair { sensor, beacon, coverage }))
+}
+
 impl FromStr for SensorBeaconPair {
-    type Err = anyhow::Error;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"Sensor at x=(-?\d+), y=(-?\d+): .* beacon .* x=(-?\d+), y=(-?\d+)").unwrap();
-        }
-        let caps = RE.captures(s).ok_or_else(|| anyhow!("Invalid input: '{}'", s))?;
-        let sx: i32 = caps[1].parse()?;
-        let sy: i32 = caps[2].parse()?;
-        let bx: i32 = caps[3].parse()?;
-        let by: i32 = caps[4].parse()?;
-        Ok(SensorBeaconPair { sx, sy, bx, by, coverage: dist((sx, sy), (bx, by)) })
+        parse_line(s, report)
     }
 }
 
-fn dist((sx, sy): (i32, i32), (dx, dy): (i32, i32)) -> i32 {
-    (sx - dx).abs() + (sy - dy).abs()
-
+impl fmt::Display for SensorBeaconPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
+            self.sensor.x, self.sensor.y, self.beacon.x, self.beacon.y
+        )
+    }
 }
 
 impl SensorBeaconPair {
-    fn occluded_range(&self, y: i32) -> RangeInclusive<i32>{
-        let ydist = (self.sy - y).abs();
-        if ydist > self.coverage {
-            return 0..=-1
+    /// The xs on row `y` that this sensor can see, if it reaches that row.
+    fn occluded_range(&self, y: i32) -> Option<RangeInclusive<i64>> {
+        let spread = self.coverage as i64 - (self.sensor.y as i64 - y as i64).abs();
+        if spread < 0 {
+            return None;
         }
-        let spread = (ydist - self.coverage).abs();
-        let range = (self.sx - spread)..=(self.sx + spread);
-        range
+        let x = self.sensor.x as i64;
+        Some((x - spread)..=(x + spread))
     }
+}
 
-    fn covered(&self, x: i32, y: i32) -> bool {
-        let range = dist((self.sx, self.sy), (x, y));
-        range <= self.coverage
-    }
+fn occluded_row(y: i32, sensors: &[SensorBeaconPair]) -> IntervalSet {
+    sensors.iter().filter_map(|sb| sb.occluded_range(y)).collect()
 }
 
-fn total_occluded(y: i32, v: &Vec<SensorBeaconPair>) -> usize {
-    let beacons: HashSet<_> = v.iter().filter_map(|sb| if sb.by == y { Some(sb.bx) } else { None }).collect();
-    let set: HashSet<_> = v.iter()
-        .flat_map(|sb| sb.occluded_range(y))
-        .filter(|x| !beacons.contains(x))
+fn total_occluded(y: i32, v: &[SensorBeaconPair]) -> usize {
+    let beacons: IntervalSet = v
+        .iter()
+        .filter(|sb| sb.beacon.y == y)
+        .map(|sb| sb.beacon.x as i64..=sb.beacon.x as i64)
         .collect();
-    set.len()
+    occluded_row(y, v).difference(&beacons).len() as usize
 }
 
-fn find_missing_beacon(max_x: i32, max_y: i32, sensors: &Vec<SensorBeaconPair>) -> Option<i64> {
+fn find_missing_beacon(max_x: i32, max_y: i32, sensors: &[SensorBeaconPair]) -> Result<Option<i64>> {
     for y in 0..=max_y {
-        let relevant: Vec<_> = sensors.iter().filter(|s| (y - s.sy).abs() < s.coverage).collect();
-        // if y % 100 == 0 {
-        //     println!("{}, {}, {}", y, relevant.len(), sensors.len());
-        // }
-        let mut ranges: Vec<_> = relevant.iter().map(|sb| sb.occluded_range(y)).collect();
-        ranges.sort_by(|r1, r2| r1.start().cmp(r2.start()));
-        let mut potential = 0;
-        let rangestr = format!("{:?}", ranges);
-        for r in ranges {
-            if r.contains(&potential) {
-                potential = r.end() + 1;
-            } else if r.start() > &potential && potential < max_x {
-                // println!("{}, ({}, {})", rangestr, potential, y);
-                return Some(4000000 as i64 * (potential as i64) + y as i64);
-            }
+        if y % 10000 == 0 {
+            check_cancelled()?;
+        }
+        if let Some(gap) = occluded_row(y, sensors).gaps(0..=max_x as i64).ranges().next() {
+            return Ok(Some(4000000 * gap.start() + y as i64));
         }
     }
-    None
+    Ok(None)
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use proptest::prelude::*;
 
     fn test_data() -> Vec<String> {
         split_str(r"Sensor at x=2, y=18: closest beacon is at x=-2, y=15
@@ -131,6 +144,39 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3")
     #[test]
     fn test_p2() {
         let sensors = parse_lines::<SensorBeaconPair>(&test_data()).unwrap();
-        assert_eq!(find_missing_beacon(20, 20, &sensors), Some(56000011));
+        assert_eq!(find_missing_beacon(20, 20, &sensors).unwrap(), Some(56000011));
+    }
+    #[test]
+    fn test_parse_error() {
+        let err = "Sensor at x=2, y=99999999999: closest beacon is at x=-2, y=15"
+            .parse::<SensorBeaconPair>()
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "column 18: expected a coordinate, found `99999999999: closest…`");
+        let mut data = test_data();
+        data[4] = "Sensor at x=10, y=20".to_string();
+        let err = parse_lines::<SensorBeaconPair>(&data).err().unwrap();
+        assert_eq!(err.downcast_ref::<ParseError>().unwrap().line, Some(5));
+    }
+
+    #[test]
+    fn test_far_beacon() {
+        let line = "Sensor at x=2147483647, y=0: closest beacon is at x=-2147483648, y=0";
+        assert_eq!(line.parse::<SensorBeaconPair>().unwrap_err().column, 53);
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_never_panics(s in "\\PC*|Sensor at x=-?[0-9]{1,12}, y=-?[0-9]{1,12}: closest beacon is at x=-?[0-9]{1,12}, y=-?[0-9]{1,12}") {
+            let _ = s.parse::<SensorBeaconPair>();
+        }
+
+        #[test]
+        fn test_display_round_trip(coords in prop::array::uniform4(-100_000_000..100_000_000)) {
+            let [sx, sy, bx, by] = coords;
+            let (sensor, beacon) = (Point::new(sx, sy), Point::new(bx, by));
+            let pair = SensorBeaconPair { sensor, beacon, coverage: sensor.manhattan(beacon) as i32 };
+            prop_assert_eq!(pair.to_string().parse::<SensorBeaconPair>().unwrap(), pair);
+        }
     }
 }
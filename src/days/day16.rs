@@ -1,80 +1,140 @@
-use std::{str::FromStr, collections::{HashMap, HashSet}};
+use std::{str::FromStr, collections::HashMap, fmt};
 
 use itertools::Itertools;
 
-use adventools::prelude::*;
+use crate::interface::*;
+use crate::parse::{comma_list, failure, key_value, lit, number, parse_line, word, PResult};
+use crate::search::Bfs;
 use anyhow::anyhow;
-use lazy_static::lazy_static;
-use regex::Regex;
+use nom::{
+    combinator::opt,
+    sequence::{preceded, tuple},
+};
 
 pub struct D {}
 
-impl DayParsed for D {}
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = ValveSet;
 
-impl Day for D {
     fn number(&self) -> u8 {
         16
     }
-    fn part01(&self) -> Result<()> {
-        println!("{}", ValveSet::new(self.input_as::<Valve>()?).best_path());
-        Ok(())
+    fn slow(&self) -> bool {
+        // part 2 searches every split of the valves between us and the elephant
+        true
+    }
+    fn parse(&self, input: &[String]) -> Result<ValveSet> {
+        ValveSet::new(parse_lines(input)?)
+    }
+    fn part01(&self, valves: &ValveSet) -> Result<Answer> {
+        Ok(valves.best_path().into())
     }
-    fn part02(&self) -> Result<()> {
-        println!("{}", ValveSet::new(self.input_as::<Valve>()?).best_path_with_help());
-        Ok(())
+    fn part02(&self, valves: &ValveSet) -> Result<Answer> {
+        Ok(valves.best_path_with_help().into())
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 struct Valve {
     id: String,
     flow_rate: usize,
     neighbors: Vec<String>,
 }
 
+/// Flow rates are kept below this so that the pressure released can't overflow.
+const MAX_FLOW: usize = 1_000_000;
+
+fn flow_rate(s: &str) -> PResult<'_, usize> {
+    let (rest, rate) = number("a flow rate")(s)?;
+    if rate >= MAX_FLOW {
+        return Err(failure(s, format!("a flow rate below {}", MAX_FLOW)));
+    }
+    Ok((rest, rate))
+}
+
+fn valve(s: &str) -> PResult<'_, Valve> {
+    let (s, id) = preceded(lit("Valve "), word("a valve id"))(s)?;
+    let (s, flow_rate) = preceded(lit(" has flow "), key_value("rate", flow_rate))(s)?;
+    // singular or plural, as the number of tunnels requires
+    let (s, _) = tuple((
+        lit("; tunnel"),
+        opt(lit("s")),
+        lit(" lead"),
+        opt(lit("s")),
+        lit(" to valve"),
+        opt(lit("s")),
+        lit(" "),
+    ))(s)?;
+    let (s, neighbors) = comma_list(word("a valve id"))(s)?;
+    let neighbors = neighbors.into_iter().map(|n| n.to_string()).collect();
+    Ok((s, Valve { id: id.to_string(), flow_rate, neighbors }))
+}
+
 impl FromStr for Valve {
-    type Err = anyhow::Error;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r"Valve (\w+) has flow rate=(\d+); tunnels? leads? to valves? (.*)").unwrap();
-        }
-        let caps = RE.captures(s).ok_or_else(|| anyhow!("Invalid input: '{}'", s))?;
-        let id = caps[1].to_string();
-        let flow_rate: usize = caps[2].parse()?;
-        let neighbors: Vec<String> = caps[3].split(", ").map(|s| s.to_string()).collect();
-        Ok(Valve { id, flow_rate, neighbors })
+        parse_line(s, valve)
     }
 }
 
-struct ValveSet {
+impl fmt::Display for Valve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tunnels = match self.neighbors.len() {
+            1 => "tunnel leads to valve",
+            _ => "tunnels lead to valves",
+        };
+        write!(
+            f,
+            "Valve {} has flow rate={}; {} {}",
+            self.id,
+            self.flow_rate,
+            tunnels,
+            self.neighbors.join(", ")
+        )
+    }
+}
+
+pub struct ValveSet {
     valves: Vec<Valve>,
     mapped: HashMap<String, Valve>,
-    distances: HashMap<String, usize>,
+    /// Steps from each valve to every valve it can reach.
+    distances: HashMap<String, HashMap<String, usize>>,
 }
 
 impl ValveSet {
-    fn new(inputs: Vec<Valve>) -> Self {
+    fn new(inputs: Vec<Valve>) -> Result<Self> {
         // println!("{:?}", inputs);
         let copied = inputs.clone();
         let mapped = map_of_valves(&copied);
+        if !mapped.contains_key("AA") {
+            Err(anyhow!("No valve AA to start from"))?;
+        }
+        for v in &copied {
+            if let Some(n) = v.neighbors.iter().find(|n| !mapped.contains_key(*n)) {
+                Err(anyhow!("Valve {} leads to unknown valve {}", v.id, n))?;
+            }
+        }
         let distances = compute_distances(&mapped);
 
-        ValveSet { valves: copied, mapped, distances }
+        Ok(ValveSet { valves: copied, mapped, distances })
     }
-    fn dist(&self, pos: &String, other: &String) -> usize {
-        self.distances[&format!("{}-{}", pos, other)]
+    fn dist(&self, pos: &str, other: &str) -> Option<usize> {
+        self.distances[pos].get(other).copied()
     }
 
     fn best_path_with_help(&self) -> usize {
         let useful_valves: Vec<_> = self.valves.iter().filter_map(|v| if v.flow_rate > 0 { Some(v.id.to_string())} else { None }).collect();
-        (1..((useful_valves.len()/2)))
+        // splits are symmetric, so each only needs trying with me taking the smaller half
+        (0..=(useful_valves.len()/2))
             .flat_map(|k| useful_valves.iter().combinations(k))
             .map(|mine| {
                 // println!("{}", mine.len());
                 let elephants: Vec<_> = useful_valves.iter().filter(|v| !mine.contains(v)).collect();
                 self.best_path_dfs("AA".to_string(), 26, 0, 0, &mine) +
                     self.best_path_dfs("AA".to_string(), 26, 0, 0, &elephants)
-            }).max().unwrap()
+            }).max().unwrap_or(0)
     }
     fn best_path(&self) -> usize {
         self.best_path_dfs("AA".to_string(), 30, 0, 0, &vec![])
@@ -82,7 +142,7 @@ impl ValveSet {
     fn worth_opening(&self, pos: &String, dest: &Valve, opened: &Vec<&String>, time_left: usize) -> bool {
         dest.flow_rate > 0 &&
             !opened.contains(&&dest.id) &&
-            self.dist(pos, &dest.id) + 1 <= time_left
+            self.dist(pos, &dest.id).is_some_and(|d| d < time_left)
     }
     fn best_path_dfs(&self, pos: String, time_left: usize, cur_flow: usize, total_flow: usize, opened: &Vec<&String>) -> usize {
         // println!("at node {} at time {} with total_flow {} having opened {} valves: {:?}", pos, time_left, total_flow, opened.len(), opened);
@@ -100,38 +160,22 @@ impl ValveSet {
             return total_flow + time_left * cur_flow;
         }
         worth_opening.iter().map(|&v| {
-            let dist = self.dist(&pos, &v.id);
+            // reachable, or it wouldn't be worth opening
+            let dist = self.dist(&pos, &v.id).unwrap();
             self.best_path_dfs(v.id.to_string(), time_left - dist, cur_flow, total_flow + cur_flow * dist, &opened)
         }).max().unwrap()
     }
 }
 
-fn compute_distances(valves: &HashMap<String, Valve>) -> HashMap<String, usize> {
-    let mut output: HashMap<String, usize> = HashMap::new();
-
-    for (k, _v) in valves {
-        let mut seen = HashSet::new();
-        seen.insert(k);
-        let mut dist = 0;
-        let mut queue = vec![k];
-        while seen.len() < valves.len() {
-            dist += 1;
-            let mut nextq = vec![];
-            for &qe in queue.iter() {
-                for ne in &valves[qe].neighbors {
-                    if !seen.contains(ne) {
-                        nextq.push(ne);
-                        output.insert(format!("{}-{}", k, ne), dist);
-                        seen.insert(ne);
-                    }
-                }
-            }
-            queue.clear();
-            queue.append(&mut nextq);
-        }
-    }
-
-    output
+fn compute_distances(valves: &HashMap<String, Valve>) -> HashMap<String, HashMap<String, usize>> {
+    valves
+        .keys()
+        .map(|k| {
+            let search = Bfs::new([k.as_str()], |&v: &&str| valves[v].neighbors.iter().map(String::as_str));
+            let reachable = search.distances().into_iter().map(|(v, d)| (v.to_string(), d)).collect();
+            (k.to_string(), reachable)
+        })
+        .collect()
 }
 
 fn map_of_valves(v: &Vec<Valve>) -> HashMap<String, Valve> {
@@ -142,6 +186,7 @@ fn map_of_valves(v: &Vec<Valve>) -> HashMap<String, Valve> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     fn test_data() -> Vec<Valve> {
         parse_lines::<Valve>(&split_str(r"Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
 Valve BB has flow rate=13; tunnels lead to valves CC, AA
@@ -157,10 +202,60 @@ Valve JJ has flow rate=21; tunnel leads to valve II")).unwrap()
 
     #[test]
     fn test_p1() {
-        assert_eq!(ValveSet::new(test_data()).best_path(), 1651);
+        assert_eq!(ValveSet::new(test_data()).unwrap().best_path(), 1651);
     }
     #[test]
     fn test_p2() {
-        assert_eq!(ValveSet::new(test_data()).best_path_with_help(), 1707);
+        assert_eq!(ValveSet::new(test_data()).unwrap().best_path_with_help(), 1707);
+    }
+    #[test]
+    fn test_parse_error() {
+        let err = "Valve AA has flow rate=x; tunnels lead to valves DD".parse::<Valve>().unwrap_err();
+        assert_eq!(err.column, 24);
+        let err = "Valve AA has flow rate=99999999999999999999; tunnel leads to valve DD"
+            .parse::<Valve>()
+            .unwrap_err();
+        assert_eq!(err.column, 24);
+        let err = "Valve AA has flow rate=1000000; tunnel leads to valve DD".parse::<Valve>().unwrap_err();
+        assert_eq!(err.to_string(), "column 24: expected a flow rate below 1000000, found `1000000; tunnel lead…`");
+
+        let mut valves = test_data();
+        valves[9].neighbors = vec!["KK".to_string()];
+        assert!(ValveSet::new(valves).is_err());
+    }
+
+    #[test]
+    fn test_unreachable_valve() {
+        let valves = parse_lines::<Valve>(&split_str(
+            "Valve AA has flow rate=0; tunnel leads to valve AA\nValve BB has flow rate=1; tunnel leads to valve BB",
+        ))
+        .unwrap();
+        let valves = ValveSet::new(valves).unwrap();
+        assert_eq!(valves.best_path(), 0);
+        assert_eq!(valves.best_path_with_help(), 0);
+    }
+
+    fn valves() -> impl Strategy<Value = Valve> {
+        let id = "[A-Z]{2}";
+        (id, 0..MAX_FLOW, prop::collection::vec(id, 1..5)).prop_map(|(id, flow_rate, neighbors)| {
+            Valve { id, flow_rate, neighbors }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_never_panics(s in "\\PC*|Valve \\w{0,3} has flow rate=[0-9]{0,25}; tunnels? leads? to valves? [A-Z, ]{0,12}") {
+            let _ = s.parse::<Valve>();
+        }
+
+        #[test]
+        fn test_display_round_trip(valve in valves()) {
+            prop_assert_eq!(valve.to_string().parse::<Valve>().unwrap(), valve);
+        }
+
+        #[test]
+        fn test_valve_set_never_panics(valves in prop::collection::vec(valves(), 0..8)) {
+            let _ = ValveSet::new(valves);
+        }
     }
 }
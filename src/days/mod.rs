@@ -1,39 +1,58 @@
-pub mod day01;
-pub mod day02;
-pub mod day03;
-pub mod day04;
-pub mod day05;
-pub mod day06;
-pub mod day07;
-pub mod day08;
-pub mod day09;
-pub mod day10;
-pub mod day11;
-pub mod day12;
-pub mod day13;
-pub mod day14;
-pub mod day15;
-pub mod day16;
-
-use crate::interface::Day;
-
-pub fn all_days() -> Vec<Box<dyn Day>> {
-    vec![
-        Box::new(day01::Day01 {}),
-        Box::new(day02::Day02 {}),
-        Box::new(day03::Day03 {}),
-        Box::new(day04::Day04 {}),
-        Box::new(day05::Day05 {}),
-        Box::new(day06::D {}),
-        Box::new(day07::D {}),
-        Box::new(day08::D {}),
-        Box::new(day09::D {}),
-        Box::new(day10::D {}),
-        Box::new(day11::D {}),
-        Box::new(day12::D {}),
-        Box::new(day13::D {}),
-        Box::new(day14::D {}),
-        // Box::new(day15::D {}), too slow
-        Box::new(day16::D {}),
-    ]
+//! Every `dayNN.rs` in this directory is found by `build.rs`, which declares
+//! its module and adds it to [`all_days`]. A day module makes itself
+//! runnable with `register_day!(TypeName);`.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::bail;
+
+use crate::interface::*;
+
+include!(concat!(env!("OUT_DIR"), "/days.rs"));
+
+pub fn all_days() -> Vec<Arc<dyn Day>> {
+    check_registry(registered()).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Makes sure each file registered the day it is named after, and that no
+/// day is registered twice.
+fn check_registry(days: Vec<(u8, Arc<dyn Day>)>) -> Result<Vec<Arc<dyn Day>>> {
+    let mut seen = HashSet::new();
+    days.into_iter()
+        .map(|(file_number, day)| {
+            if day.number() != file_number {
+                bail!(
+                    "day{:02}.rs registers day {}, not {}",
+                    file_number,
+                    day.number(),
+                    file_number
+                );
+            }
+            if !seen.insert(day.number()) {
+                bail!("Day {} is registered twice", day.number());
+            }
+            Ok(day)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        // new days may be scaffolded at any time, so only the ones already
+        // solved are pinned down
+        let numbers: Vec<u8> = all_days().iter().map(|d| d.number()).collect();
+        assert!(numbers.windows(2).all(|w| w[0] < w[1]), "{:?}", numbers);
+        assert!((1..=16).all(|n| numbers.contains(&n)), "{:?}", numbers);
+    }
+
+    #[test]
+    fn test_check_registry() {
+        assert!(check_registry(vec![(2, day01::register())]).is_err());
+        assert!(check_registry(vec![(1, day01::register()), (1, day01::register())]).is_err());
+    }
 }
@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::interface::*;
+use crate::runner::solve;
+use crate::verify::Verdict;
+
+/// Where example fixtures live, relative to the crate root.
+pub const DIR: &str = "fixtures";
+
+/// An example from a puzzle description, with the answers it should give.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Example {
+    pub input: Vec<String>,
+    pub part1: Option<String>,
+    pub part2: Option<String>,
+}
+
+impl Example {
+    pub fn expected(&self, part: u8) -> Option<&str> {
+        match part {
+            1 => self.part1.as_deref(),
+            2 => self.part2.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// The fixture file for `day` under the crate root `root`.
+pub fn path(root: &Path, day: u8) -> PathBuf {
+    root.join(DIR).join(format!("day{:02}.txt", day))
+}
+
+/// One `--- name [value]` header and the lines up to the next one.
+struct Section<'a> {
+    line: usize,
+    header: &'a str,
+    name: &'a str,
+    value: Option<&'a str>,
+    body: Vec<String>,
+}
+
+/// Parses a fixture file, which holds one or more examples:
+///
+/// ```text
+/// Anything before the first section is a comment.
+/// --- input
+/// A Y
+/// B X
+/// C Z
+/// --- part1 15
+/// --- part2 12
+/// ```
+///
+/// Either answer may be left out. A multi-line answer, such as a picture,
+/// goes on the lines after a bare `--- partN`; trailing whitespace on those
+/// lines is ignored. Blank lines at the end of every section are dropped.
+pub fn parse(text: &str) -> Result<Vec<Example>> {
+    let mut sections: Vec<Section> = vec![];
+    for (n, line) in text.lines().enumerate() {
+        if let Some(rest) = line.strip_prefix("--- ") {
+            let (name, value) = match rest.split_once(' ') {
+                Some((name, value)) => (name, Some(value.trim())),
+                None => (rest, None),
+            };
+            sections.push(Section {
+                line: n + 1,
+                header: line,
+                name,
+                value,
+                body: vec![],
+            });
+        } else if let Some(section) = sections.last_mut() {
+            section.body.push(line.to_string());
+        }
+    }
+
+    let mut examples: Vec<Example> = vec![];
+    for mut s in sections {
+        while s.body.last().is_some_and(|l| l.is_empty()) {
+            s.body.pop();
+        }
+        let err = |expected: &str, offset: usize| ParseError::new(expected, s.header, offset).at_line(s.line);
+        match s.name {
+            "input" => {
+                if s.value.is_some() {
+                    Err(err("end of line", "--- input".len()))?;
+                }
+                examples.push(Example {
+                    input: s.body,
+                    ..Example::default()
+                });
+            }
+            "part1" | "part2" => {
+                let example = examples
+                    .last_mut()
+                    .ok_or_else(|| err("`--- input` before any answers", 0))?;
+                let answer = match (s.value, s.body.is_empty()) {
+                    (Some(value), true) => value.to_string(),
+                    (None, false) => s.body.iter().map(|l| l.trim_end()).collect::<Vec<_>>().join("\n"),
+                    (Some(_), false) => {
+                        Err(ParseError::new("the next section", &s.body[0], 0).at_line(s.line + 1))?
+                    }
+                    (None, true) => Err(err("an answer", s.header.len()))?,
+                };
+                let slot = if s.name == "part1" { &mut example.part1 } else { &mut example.part2 };
+                if slot.is_some() {
+                    Err(err("one answer per part and example", 4))?;
+                }
+                *slot = Some(answer);
+            }
+            _ => Err(err("`input`, `part1` or `part2`", 4))?,
+        }
+    }
+    Ok(examples)
+}
+
+pub fn read(path: &Path) -> Result<Vec<Example>> {
+    let text = fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+    parse(&text).with_context(|| format!("Reading {}", path.display()))
+}
+
+/// Loads the examples for `day` from this crate's fixtures directory.
+pub fn load(day: u8) -> Result<Vec<Example>> {
+    read(&path(Path::new(env!("CARGO_MANIFEST_DIR")), day))
+}
+
+fn trim_picture(answer: Answer) -> Answer {
+    match answer {
+        Answer::Picture(lines) => {
+            Answer::Picture(lines.iter().map(|l| l.trim_end().to_string()).collect())
+        }
+        answer => answer,
+    }
+}
+
+/// Solves `example` with `day` and compares each part that has an expected
+/// answer.
+pub fn check(day: &dyn Day, example: &Example) -> Result<Vec<(u8, Verdict)>> {
+    let parsed = day.parse(&example.input).context("Parsing example input")?;
+    Ok([1, 2]
+        .into_iter()
+        .filter_map(|part| {
+            let expected = example.expected(part)?;
+            let actual = solve(day, part, &*parsed).map(trim_picture);
+            Some((part, Verdict::check(Some(expected), actual)))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::all_days;
+
+    #[test]
+    fn test_parse() {
+        let text = "Two examples.\n--- input\nA Y\n\n--- part1 15\n\n--- input\nB X\n--- part2\n# \n.#\n";
+        let examples = parse(text).unwrap();
+        assert_eq!(
+            examples,
+            vec![
+                Example {
+                    input: vec!["A Y".to_string()],
+                    part1: Some("15".to_string()),
+                    part2: None,
+                },
+                Example {
+                    input: vec!["B X".to_string()],
+                    part1: None,
+                    part2: Some("#\n.#".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse("--- part1 15\n").unwrap_err();
+        assert_eq!(err.downcast_ref::<ParseError>().unwrap().line, Some(1));
+        let err = parse("--- input\n1\n--- part3 2\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 5: expected `input`, `part1` or `part2`, found `part3 2`"
+        );
+        assert!(parse("--- input\n1\n--- part1 2\n3\n").is_err());
+        assert!(parse("--- input\n1\n--- part1\n").is_err());
+        assert!(parse("--- input\n1\n--- part1 2\n--- part1 3\n").is_err());
+    }
+
+    /// Runs every registered day against the examples in its fixture.
+    #[test]
+    fn test_all_days() {
+        let mut failures = vec![];
+        for day in all_days() {
+            let examples = match load(day.number()) {
+                Ok(examples) if examples.is_empty() => {
+                    failures.push(format!("Day {:02}: no examples", day.number()));
+                    continue;
+                }
+                Ok(examples) => examples,
+                Err(e) => {
+                    failures.push(format!("Day {:02}: {:#}", day.number(), e));
+                    continue;
+                }
+            };
+            for (i, example) in examples.iter().enumerate() {
+                match check(day.as_ref(), example) {
+                    Ok(verdicts) => {
+                        for (part, verdict) in verdicts.into_iter().filter(|(_, v)| v.is_failure()) {
+                            failures.push(format!(
+                                "Day {:02} example {} part {}: {}",
+                                day.number(),
+                                i + 1,
+                                part,
+                                verdict
+                            ));
+                        }
+                    }
+                    Err(e) => failures.push(format!("Day {:02} example {}: {:#}", day.number(), i + 1, e)),
+                }
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
@@ -0,0 +1,390 @@
+//! A hook that lets simulations show their state as they go. Simulation
+//! code calls [`emit`] once per step with a closure that draws the current
+//! state as lines of text; the closure only runs when `--frames` has
+//! installed a [`Sink`].
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use crossterm::terminal::{Clear, ClearType};
+
+use crate::interface::*;
+
+/// How long each frame stays up in a terminal animation or GIF.
+pub const FRAME_DELAY: Duration = Duration::from_millis(40);
+
+/// Where frames go, as chosen with `--frames`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// Animated in the terminal with ANSI escapes.
+    Ansi,
+    /// Every frame written to a text file.
+    Text(PathBuf),
+    /// An animated GIF.
+    Gif(PathBuf),
+}
+
+impl FromStr for Target {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Target> {
+        match s.split_once(':') {
+            None if s == "ansi" => Ok(Target::Ansi),
+            Some(("text", path)) if !path.is_empty() => Ok(Target::Text(path.into())),
+            Some(("gif", path)) if !path.is_empty() => Ok(Target::Gif(path.into())),
+            _ => Err(anyhow!("Invalid frame target '{}', expected ansi, text:PATH or gif:PATH", s)),
+        }
+    }
+}
+
+impl Target {
+    pub fn open(&self) -> Result<Box<dyn Sink>> {
+        let create = |path: &PathBuf| {
+            File::create(path)
+                .map(BufWriter::new)
+                .with_context(|| format!("Writing {}", path.display()))
+        };
+        Ok(match self {
+            Target::Ansi => Box::new(AnsiSink::new(io::stdout(), FRAME_DELAY)),
+            Target::Text(path) => Box::new(TextSink::new(create(path)?)),
+            Target::Gif(path) => Box::new(GifSink::new(create(path)?)),
+        })
+    }
+}
+
+/// Somewhere to send frames.
+pub trait Sink: Send {
+    fn frame(&mut self, lines: &[String]) -> Result<()>;
+
+    /// Called once after the last frame.
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Redraws each frame in place on a terminal.
+pub struct AnsiSink<W> {
+    out: W,
+    delay: Duration,
+}
+
+impl<W: Write> AnsiSink<W> {
+    pub fn new(out: W, delay: Duration) -> Self {
+        AnsiSink { out, delay }
+    }
+}
+
+impl<W: Write + Send> Sink for AnsiSink<W> {
+    fn frame(&mut self, lines: &[String]) -> Result<()> {
+        queue!(self.out, MoveTo(0, 0), Clear(ClearType::All))?;
+        for line in lines {
+            writeln!(self.out, "{}", line)?;
+        }
+        self.out.flush()?;
+        thread::sleep(self.delay);
+        Ok(())
+    }
+}
+
+/// Writes every frame one after another, each under a `--- frame N` header.
+pub struct TextSink<W> {
+    out: W,
+    count: usize,
+}
+
+impl<W: Write> TextSink<W> {
+    pub fn new(out: W) -> Self {
+        TextSink { out, count: 0 }
+    }
+}
+
+impl<W: Write + Send> Sink for TextSink<W> {
+    fn frame(&mut self, lines: &[String]) -> Result<()> {
+        self.count += 1;
+        writeln!(self.out, "--- frame {}", self.count)?;
+        for line in lines {
+            writeln!(self.out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+}
+
+/// Background first, then a color for each other character in the order
+/// they turn up.
+const PALETTE: [[u8; 3]; 16] = [
+    [0x0f, 0x0f, 0x23],
+    [0xff, 0xff, 0x66],
+    [0xcc, 0xcc, 0xcc],
+    [0x00, 0x99, 0x00],
+    [0xff, 0x66, 0x00],
+    [0x33, 0x99, 0xff],
+    [0xff, 0x33, 0x66],
+    [0x99, 0x66, 0x33],
+    [0x66, 0xff, 0xcc],
+    [0xcc, 0x66, 0xff],
+    [0xff, 0xcc, 0x99],
+    [0x66, 0x66, 0x66],
+    [0x99, 0xff, 0x33],
+    [0x33, 0x33, 0x99],
+    [0xff, 0x99, 0xcc],
+    [0xff, 0xff, 0xff],
+];
+
+/// Frames kept before every other one is dropped, to bound memory use.
+const MAX_GIF_FRAMES: usize = 1000;
+
+/// Pixels per character, across and down.
+const GIF_SCALE: usize = 3;
+
+/// Collects frames and writes them as an animated GIF when finished, one
+/// colored square per character. The canvas is as big as the biggest frame.
+/// Long runs are thinned out to at most [`MAX_GIF_FRAMES`] frames.
+pub struct GifSink<W> {
+    out: W,
+    colors: HashMap<char, u8>,
+    /// Each kept frame as rows of palette indices.
+    frames: Vec<Vec<Vec<u8>>>,
+    stride: usize,
+    offered: usize,
+}
+
+impl<W: Write> GifSink<W> {
+    pub fn new(out: W) -> Self {
+        GifSink {
+            out,
+            colors: HashMap::new(),
+            frames: vec![],
+            stride: 1,
+            offered: 0,
+        }
+    }
+
+    fn color(&mut self, c: char) -> u8 {
+        if c == ' ' || c == '.' {
+            return 0;
+        }
+        let next = (self.colors.len() % (PALETTE.len() - 1) + 1) as u8;
+        *self.colors.entry(c).or_insert(next)
+    }
+}
+
+impl<W: Write + Send> Sink for GifSink<W> {
+    fn frame(&mut self, lines: &[String]) -> Result<()> {
+        self.offered += 1;
+        if !(self.offered - 1).is_multiple_of(self.stride) {
+            return Ok(());
+        }
+        let frame = lines
+            .iter()
+            .map(|line| line.chars().map(|c| self.color(c)).collect())
+            .collect();
+        self.frames.push(frame);
+        if self.frames.len() == MAX_GIF_FRAMES {
+            let mut n = 0;
+            self.frames.retain(|_| {
+                n += 1;
+                n % 2 == 1
+            });
+            self.stride *= 2;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let cols = self.frames.iter().flatten().map(|row| row.len()).max().unwrap_or(1);
+        let rows = self.frames.iter().map(|frame| frame.len()).max().unwrap_or(1);
+        let (width, height) = (cols * GIF_SCALE, rows * GIF_SCALE);
+        let size = |n: usize| u16::try_from(n).map_err(|_| anyhow!("Frames are too big for a GIF"));
+        let palette: Vec<u8> = PALETTE.iter().flatten().copied().collect();
+        let mut encoder = gif::Encoder::new(&mut self.out, size(width)?, size(height)?, &palette)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        for frame in &self.frames {
+            let mut pixels = vec![0; width * height];
+            for (y, row) in frame.iter().enumerate() {
+                for (x, &color) in row.iter().enumerate() {
+                    for dy in 0..GIF_SCALE {
+                        let start = (y * GIF_SCALE + dy) * width + x * GIF_SCALE;
+                        pixels[start..start + GIF_SCALE].fill(color);
+                    }
+                }
+            }
+            let gif_frame = gif::Frame {
+                width: size(width)?,
+                height: size(height)?,
+                delay: (FRAME_DELAY.as_millis() / 10) as u16,
+                buffer: Cow::Owned(pixels),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&gif_frame)?;
+        }
+        drop(encoder);
+        Ok(self.out.flush()?)
+    }
+}
+
+/// An installed sink and how many frames it has been offered.
+pub struct Hook {
+    sink: Box<dyn Sink>,
+    every: usize,
+    offered: usize,
+    /// The first error from the sink; later frames are dropped.
+    error: Option<anyhow::Error>,
+}
+
+impl Hook {
+    /// Sends every `every`th frame to `sink`.
+    pub fn new(sink: Box<dyn Sink>, every: usize) -> Hook {
+        Hook {
+            sink,
+            every: every.max(1),
+            offered: 0,
+            error: None,
+        }
+    }
+
+    pub fn offer(&mut self, render: impl FnOnce() -> Vec<String>) {
+        self.offered += 1;
+        if self.error.is_some() || !(self.offered - 1).is_multiple_of(self.every) {
+            return;
+        }
+        if let Err(e) = self.sink.frame(&render()) {
+            self.error = Some(e);
+        }
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => self.sink.finish(),
+        }
+    }
+}
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static HOOK: Mutex<Option<Hook>> = Mutex::new(None);
+
+/// Sends frames from every thread to `hook` until [`finish`] is called.
+pub fn install(hook: Hook) {
+    *HOOK.lock().unwrap() = Some(hook);
+    ACTIVE.store(true, Ordering::Relaxed);
+}
+
+/// Offers a frame to the installed sink, if there is one. `render` is only
+/// called when the frame is wanted.
+pub fn emit(render: impl FnOnce() -> Vec<String>) {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(hook) = HOOK.lock().unwrap().as_mut() {
+        hook.offer(render);
+    }
+}
+
+/// Removes the installed sink and lets it finish writing.
+pub fn finish() -> Result<()> {
+    ACTIVE.store(false, Ordering::Relaxed);
+    match HOOK.lock().unwrap().take() {
+        Some(hook) => hook.finish(),
+        None => Ok(()),
+    }
+}
+
+/// Draws `width` by `height` cells, taking the character for each from
+/// `cell(x, y)`.
+pub fn draw(width: usize, height: usize, cell: impl Fn(usize, usize) -> char) -> Vec<String> {
+    (0..height)
+        .map(|y| (0..width).map(|x| cell(x, y)).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    /// Keeps frames where a test can see them.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<Vec<String>>>>);
+
+    impl Sink for Capture {
+        fn frame(&mut self, lines: &[String]) -> Result<()> {
+            self.0.lock().unwrap().push(lines.to_vec());
+            Ok(())
+        }
+    }
+
+    fn frame(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_target() {
+        assert_eq!("ansi".parse::<Target>().unwrap(), Target::Ansi);
+        assert_eq!("gif:out.gif".parse::<Target>().unwrap(), Target::Gif("out.gif".into()));
+        assert_eq!("text:f.txt".parse::<Target>().unwrap(), Target::Text("f.txt".into()));
+        assert!("gif".parse::<Target>().is_err());
+        assert!("png:x.png".parse::<Target>().is_err());
+    }
+
+    #[test]
+    fn test_hook() {
+        let capture = Capture::default();
+        let mut hook = Hook::new(Box::new(capture.clone()), 2);
+        let mut rendered = 0;
+        for n in 0..5 {
+            hook.offer(|| {
+                rendered += 1;
+                vec![n.to_string()]
+            });
+        }
+        assert_eq!(rendered, 3);
+        assert_eq!(*capture.0.lock().unwrap(), vec![frame(&["0"]), frame(&["2"]), frame(&["4"])]);
+        assert!(hook.finish().is_ok());
+    }
+
+    #[test]
+    fn test_text_sink() {
+        let mut sink = TextSink::new(vec![]);
+        sink.frame(&frame(&["#.", ".#"])).unwrap();
+        sink.frame(&frame(&["##"])).unwrap();
+        sink.finish().unwrap();
+        let text = String::from_utf8(sink.out).unwrap();
+        assert_eq!(text, "--- frame 1\n#.\n.#\n--- frame 2\n##\n");
+    }
+
+    #[test]
+    fn test_gif_sink() {
+        let mut sink = GifSink::new(vec![]);
+        for n in 0..MAX_GIF_FRAMES + 1 {
+            let row = if n % 2 == 0 { "#." } else { ".o" };
+            sink.frame(&frame(&[row, "#"])).unwrap();
+        }
+        assert_eq!(sink.stride, 2);
+        assert_eq!(sink.frames.len(), MAX_GIF_FRAMES / 2 + 1);
+        assert_eq!(sink.frames[0], vec![vec![1, 0], vec![1]]);
+        sink.finish().unwrap();
+        assert!(sink.out.starts_with(b"GIF89a"));
+        assert_eq!(sink.out.last(), Some(&0x3b));
+    }
+
+    #[test]
+    fn test_draw() {
+        assert_eq!(draw(3, 2, |x, y| if x == y { '#' } else { '.' }), frame(&["#..", ".#."]));
+    }
+}
@@ -0,0 +1,206 @@
+//! Points and directions on the integer plane, for the days that move
+//! things around grids. `y` grows downwards, as in the puzzle drawings.
+
+use std::fmt;
+use std::num::TryFromIntError;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// The four ways to step to an orthogonal neighbour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Offsets of the eight neighbours of a point, clockwise from the one above.
+const AROUND: [Point; 8] = [
+    Point::new(0, -1),
+    Point::new(1, -1),
+    Point::new(1, 0),
+    Point::new(1, 1),
+    Point::new(0, 1),
+    Point::new(-1, 1),
+    Point::new(-1, 0),
+    Point::new(-1, -1),
+];
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+    /// The step this direction takes.
+    pub const fn delta(self) -> Point {
+        match self {
+            Direction::Up => Point::new(0, -1),
+            Direction::Down => Point::new(0, 1),
+            Direction::Left => Point::new(-1, 0),
+            Direction::Right => Point::new(1, 0),
+        }
+    }
+
+    /// Reads the `U`, `D`, `L` and `R` used by puzzle inputs.
+    pub fn from_letter(c: char) -> Option<Direction> {
+        match c {
+            'U' => Some(Direction::Up),
+            'D' => Some(Direction::Down),
+            'L' => Some(Direction::Left),
+            'R' => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point::new(0, 0);
+
+    pub const fn new(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+
+    /// Steps along the grid lines, as a rook moving one square at a time.
+    pub fn manhattan(self, other: Point) -> i64 {
+        (self.x as i64 - other.x as i64).abs() + (self.y as i64 - other.y as i64).abs()
+    }
+
+    /// Steps including diagonals, as a king moves.
+    pub fn chebyshev(self, other: Point) -> i64 {
+        let dx = (self.x as i64 - other.x as i64).abs();
+        let dy = (self.y as i64 - other.y as i64).abs();
+        dx.max(dy)
+    }
+
+    /// Each coordinate reduced to -1, 0 or 1.
+    pub fn signum(self) -> Point {
+        Point::new(self.x.signum(), self.y.signum())
+    }
+
+    pub fn step(self, dir: Direction) -> Point {
+        self + dir.delta()
+    }
+
+    /// The points beyond this one in `dir`, nearest first, without end.
+    pub fn walk(self, dir: Direction) -> impl Iterator<Item = Point> {
+        let delta = dir.delta();
+        std::iter::successors(Some(self + delta), move |&p| Some(p + delta))
+    }
+
+    /// The points above, right of, below and left of this one.
+    pub fn neighbors4(self) -> impl Iterator<Item = Point> {
+        Direction::ALL.into_iter().map(move |dir| self.step(dir))
+    }
+
+    /// The eight points around this one, diagonals included.
+    pub fn neighbors8(self) -> impl Iterator<Item = Point> {
+        AROUND.into_iter().map(move |delta| self + delta)
+    }
+
+    /// This point as indices into a `width` by `height` grid, if it lies
+    /// within it.
+    pub fn within(self, width: usize, height: usize) -> Option<(usize, usize)> {
+        let x = usize::try_from(self.x).ok().filter(|&x| x < width)?;
+        let y = usize::try_from(self.y).ok().filter(|&y| y < height)?;
+        Some((x, y))
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl From<(i32, i32)> for Point {
+    fn from((x, y): (i32, i32)) -> Point {
+        Point::new(x, y)
+    }
+}
+
+impl TryFrom<(usize, usize)> for Point {
+    type Error = TryFromIntError;
+
+    fn try_from((x, y): (usize, usize)) -> Result<Point, TryFromIntError> {
+        Ok(Point::new(x.try_into()?, y.try_into()?))
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, other: Point) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i32> for Point {
+    type Output = Point;
+
+    fn mul(self, n: i32) -> Point {
+        Point::new(self.x * n, self.y * n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distances() {
+        let (a, b) = (Point::new(2, 18), Point::new(-2, 15));
+        assert_eq!(a.manhattan(b), 7);
+        assert_eq!(a.chebyshev(b), 4);
+        assert_eq!((a - b).signum(), Point::new(1, 1));
+        let far = Point::new(i32::MIN, i32::MIN).manhattan(Point::new(i32::MAX, i32::MAX));
+        assert_eq!(far, 2 * (u32::MAX as i64));
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let p = Point::new(1, 1);
+        let four: Vec<_> = p.neighbors4().collect();
+        assert_eq!(four, vec![Point::new(1, 0), Point::new(2, 1), Point::new(1, 2), Point::new(0, 1)]);
+        assert_eq!(p.neighbors8().count(), 8);
+        assert!(p.neighbors8().all(|n| n.chebyshev(p) == 1));
+        let ray: Vec<_> = p.walk(Direction::Left).take(3).collect();
+        assert_eq!(ray, vec![Point::new(0, 1), Point::new(-1, 1), Point::new(-2, 1)]);
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Point::new(2, 3).within(3, 4), Some((2, 3)));
+        assert_eq!(Point::new(3, 3).within(3, 4), None);
+        assert_eq!(Point::new(-1, 0).within(3, 4), None);
+        assert_eq!(Point::try_from((5usize, 7usize)), Ok(Point::new(5, 7)));
+        assert!(Point::try_from((usize::MAX, 0usize)).is_err());
+        assert_eq!(Direction::from_letter('L').map(Direction::delta), Some(Point::new(-1, 0)));
+        assert_eq!(Point::new(1, -2) * 3, Point::new(3, -6));
+    }
+}
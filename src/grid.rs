@@ -0,0 +1,329 @@
+//! A rectangle of cells, as drawn in so many puzzle inputs. Cells are
+//! addressed either by `(x, y)` indices or by [`Point`], with `y` growing
+//! downwards.
+
+use std::ops::{Index, IndexMut, Range};
+
+use crate::geometry::{Direction, Point};
+use crate::parse::ParseError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    /// Row by row, top first.
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// A grid whose cells are `cell(x, y)`.
+    pub fn from_fn(width: usize, height: usize, mut cell: impl FnMut(usize, usize) -> T) -> Grid<T> {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| cell(x, y))
+            .collect();
+        Grid { width, height, cells }
+    }
+
+    /// Reads one cell per character, converting each with `T::try_from`.
+    /// A failed conversion's error says what was expected there.
+    pub fn parse(lines: &[String]) -> Result<Grid<T>, ParseError>
+    where
+        T: TryFrom<char>,
+        T::Error: ToString,
+    {
+        let width = lines.first().map_or(0, |row| row.chars().count());
+        if width == 0 {
+            let found = lines.first().map_or("", String::as_str);
+            return Err(ParseError::new("a row of cells", found, 0).at_line(1));
+        }
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for (n, row) in lines.iter().enumerate() {
+            for (i, c) in row.char_indices() {
+                let cell = T::try_from(c)
+                    .map_err(|e| ParseError::new(e.to_string(), row, i).at_line(n + 1))?;
+                cells.push(cell);
+            }
+            if cells.len() != width * (n + 1) {
+                let expected = format!("a row of width {}", width);
+                let offset = row.char_indices().nth(width).map_or(row.len(), |(i, _)| i);
+                return Err(ParseError::new(expected, row, offset).at_line(n + 1));
+            }
+        }
+        Ok(Grid {
+            width,
+            height: lines.len(),
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        p.within(self.width, self.height).is_some()
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        let xy = p.within(self.width, self.height)?;
+        Some(&self[xy])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        let xy = p.within(self.width, self.height)?;
+        Some(&mut self[xy])
+    }
+
+    /// Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| (Point::new((i % width) as i32, (i / width) as i32), cell))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {} of a grid {} wide", x, self.width);
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    /// The cells beyond `from` in `dir`, nearest first, up to the edge.
+    pub fn ray(&self, from: Point, dir: Direction) -> impl Iterator<Item = &T> {
+        from.walk(dir).map_while(|p| self.get(p))
+    }
+
+    /// The orthogonal neighbours of `p` that lie within the grid.
+    pub fn neighbors4(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        p.neighbors4().filter(|&n| self.contains(n))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// The grid reflected in its leading diagonal, so rows become columns.
+    pub fn transpose(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid::from_fn(self.height, self.width, |x, y| self[(y, x)].clone())
+    }
+
+    /// The grid turned a quarter turn clockwise.
+    pub fn rotate_right(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid::from_fn(self.height, self.width, |x, y| self[(y, self.height - 1 - x)].clone())
+    }
+
+    /// The grid turned a quarter turn anticlockwise.
+    pub fn rotate_left(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid::from_fn(self.height, self.width, |x, y| self[(self.width - 1 - y, x)].clone())
+    }
+
+    /// The cells in columns `xs` of rows `ys`, without copying them.
+    pub fn view(&self, xs: Range<usize>, ys: Range<usize>) -> View<'_, T> {
+        assert!(
+            xs.start <= xs.end && xs.end <= self.width && ys.start <= ys.end && ys.end <= self.height,
+            "view {:?} x {:?} of a {}x{} grid",
+            xs,
+            ys,
+            self.width,
+            self.height
+        );
+        View { grid: self, xs, ys }
+    }
+
+    fn check(&self, x: usize, y: usize) {
+        // a bad x would otherwise silently wrap onto the next row
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) outside a {}x{} grid",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+    }
+
+    /// One line of text per row, taking each cell's character from `cell`.
+    pub fn render(&self, cell: impl FnMut(&T) -> char) -> Vec<String> {
+        self.view(0..self.width, 0..self.height).render(cell)
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.check(x, y);
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        self.check(x, y);
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Point) -> &T {
+        self.get(p)
+            .unwrap_or_else(|| panic!("{} outside a {}x{} grid", p, self.width, self.height))
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        self.get_mut(p)
+            .unwrap_or_else(|| panic!("{} outside a {}x{} grid", p, width, height))
+    }
+}
+
+/// A rectangle borrowed from a [`Grid`], indexed from its own top left.
+pub struct View<'a, T> {
+    grid: &'a Grid<T>,
+    xs: Range<usize>,
+    ys: Range<usize>,
+}
+
+impl<'a, T> View<'a, T> {
+    pub fn width(&self) -> usize {
+        self.xs.len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.ys.len()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&'a T> {
+        if x < self.width() && y < self.height() {
+            Some(&self.grid[(self.xs.start + x, self.ys.start + y)])
+        } else {
+            None
+        }
+    }
+
+    pub fn render(&self, mut cell: impl FnMut(&T) -> char) -> Vec<String> {
+        self.ys
+            .clone()
+            .map(|y| self.xs.clone().map(|x| cell(&self.grid[(x, y)])).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::split_str;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Digit(u8);
+
+    impl TryFrom<char> for Digit {
+        type Error = &'static str;
+
+        fn try_from(c: char) -> Result<Digit, &'static str> {
+            c.to_digit(10).map(|d| Digit(d as u8)).ok_or("a digit")
+        }
+    }
+
+    fn digits() -> Grid<Digit> {
+        Grid::parse(&split_str("123\n456")).unwrap()
+    }
+
+    fn show(grid: &Grid<Digit>) -> Vec<String> {
+        grid.render(|d| (b'0' + d.0) as char)
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = digits();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 1)], Digit(6));
+        assert_eq!(grid[Point::new(0, 1)], Digit(4));
+
+        let err = Grid::<Digit>::parse(&split_str("123\n4x6")).unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 2: expected a digit, found `x6`");
+        let err = Grid::<Digit>::parse(&split_str("123\n4567")).unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 4: expected a row of width 3, found `7`");
+        let err = Grid::<Digit>::parse(&split_str("123\n45")).unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 3: expected a row of width 3, found end of line");
+        assert!(Grid::<Digit>::parse(&[]).is_err());
+    }
+
+    #[test]
+    fn test_iteration() {
+        let grid = digits();
+        assert_eq!(grid.row(1), &[Digit(4), Digit(5), Digit(6)]);
+        assert_eq!(grid.rows().count(), 2);
+        assert_eq!(grid.column(1).collect::<Vec<_>>(), vec![&Digit(2), &Digit(5)]);
+        let ray: Vec<_> = grid.ray(Point::new(2, 1), Direction::Left).collect();
+        assert_eq!(ray, vec![&Digit(5), &Digit(4)]);
+        assert_eq!(grid.ray(Point::new(2, 1), Direction::Down).count(), 0);
+        let around: Vec<_> = grid.neighbors4(Point::new(0, 0)).collect();
+        assert_eq!(around, vec![Point::new(1, 0), Point::new(0, 1)]);
+        let (p, d) = grid.iter().nth(4).unwrap();
+        assert_eq!((p, *d), (Point::new(1, 1), Digit(5)));
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+    }
+
+    #[test]
+    fn test_transforms() {
+        let grid = digits();
+        assert_eq!(show(&grid.transpose()), vec!["14", "25", "36"]);
+        assert_eq!(show(&grid.rotate_right()), vec!["41", "52", "63"]);
+        assert_eq!(show(&grid.rotate_left()), vec!["36", "25", "14"]);
+        assert_eq!(grid.rotate_right().rotate_left(), grid);
+        let doubled = grid.map(|d| d.0 * 2);
+        assert_eq!(doubled.row(1), &[8, 10, 12]);
+    }
+
+    #[test]
+    fn test_view() {
+        let grid = Grid::from_fn(4, 3, |x, y| Digit((x + y * 4) as u8 % 10));
+        let view = grid.view(1..3, 1..3);
+        assert_eq!((view.width(), view.height()), (2, 2));
+        assert_eq!(view.get(0, 0), Some(&Digit(5)));
+        assert_eq!(view.get(2, 0), None);
+        assert_eq!(view.render(|d| (b'0' + d.0) as char), vec!["56", "90"]);
+    }
+}
@@ -0,0 +1,189 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::cli::Options;
+use crate::interface::*;
+
+/// Environment variable overriding the default input directory.
+pub const DIR_VAR: &str = "ADVENT_INPUT_DIR";
+
+pub fn file_name(day: u8) -> String {
+    format!("input{:02}.txt", day)
+}
+
+pub fn read_input(path: &Path) -> Result<Vec<String>> {
+    let file = fs::File::open(path).with_context(|| format!("Reading {}", path.display()))?;
+    read_lines_from(file).with_context(|| format!("Reading {}", path.display()))
+}
+
+pub fn read_lines_from<R: Read>(reader: R) -> Result<Vec<String>> {
+    Ok(BufReader::new(reader).lines().collect::<io::Result<_>>()?)
+}
+
+/// Where to find the puzzle input for a single run over the selected days.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputSet {
+    /// Name of the input set, e.g. the account the inputs belong to.
+    pub name: Option<String>,
+    source: Source,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Source {
+    /// `inputNN.txt` files inside a directory.
+    Dir(PathBuf),
+    /// One file, used whichever day asks for it.
+    File(PathBuf),
+    /// Lines already read from somewhere else, such as stdin.
+    Memory { label: String, lines: Vec<String> },
+}
+
+impl InputSet {
+    pub fn dir(path: impl Into<PathBuf>) -> InputSet {
+        InputSet {
+            name: None,
+            source: Source::Dir(path.into()),
+        }
+    }
+
+    pub fn file(path: impl Into<PathBuf>) -> InputSet {
+        InputSet {
+            name: None,
+            source: Source::File(path.into()),
+        }
+    }
+
+    /// Reads all of `reader` up front, so that every day and every
+    /// iteration sees the same input.
+    pub fn from_reader<R: Read>(label: &str, reader: R) -> Result<InputSet> {
+        Ok(InputSet::lines(label, read_lines_from(reader)?))
+    }
+
+    pub fn lines(label: &str, lines: Vec<String>) -> InputSet {
+        InputSet {
+            name: None,
+            source: Source::Memory {
+                label: label.to_string(),
+                lines,
+            },
+        }
+    }
+
+    /// The file a day's input is read from, if it comes from a file at all.
+    pub fn path(&self, day: u8) -> Option<PathBuf> {
+        match &self.source {
+            Source::Dir(dir) => Some(dir.join(file_name(day))),
+            Source::File(path) => Some(path.clone()),
+            Source::Memory { .. } => None,
+        }
+    }
+
+    pub fn describe(&self, day: u8) -> String {
+        match (&self.source, self.path(day)) {
+            (Source::Memory { label, .. }, _) => label.clone(),
+            (_, Some(path)) => path.display().to_string(),
+            (_, None) => unreachable!(),
+        }
+    }
+
+    pub fn load(&self, day: u8) -> Result<Vec<String>> {
+        match (&self.source, self.path(day)) {
+            (Source::Memory { lines, .. }, _) => Ok(lines.clone()),
+            (_, Some(path)) => read_input(&path),
+            (_, None) => unreachable!(),
+        }
+    }
+
+    /// The answer registry belonging to this set. Named sets keep their own
+    /// `answers.toml` next to their inputs, since answers differ per account.
+    pub fn answers_path(&self, default: &Path) -> PathBuf {
+        match (&self.name, &self.source) {
+            (Some(_), Source::Dir(dir)) => dir.join(crate::answers::DEFAULT_PATH),
+            _ => default.to_path_buf(),
+        }
+    }
+}
+
+/// Resolves the input sets to run against. `--input` wins over everything
+/// (with `-` meaning stdin), then `--input-dir`, then the `ADVENT_INPUT_DIR`
+/// environment variable, then the current directory. Each `--input-set NAME`
+/// is a subdirectory of the resolved input directory.
+pub fn resolve(opts: &Options) -> Result<Vec<InputSet>> {
+    if opts.input.as_deref() == Some(Path::new("-")) {
+        return Ok(vec![InputSet::from_reader("<stdin>", io::stdin().lock())?]);
+    }
+    Ok(resolve_with(opts, env::var_os(DIR_VAR).map(PathBuf::from)))
+}
+
+fn resolve_with(opts: &Options, env_dir: Option<PathBuf>) -> Vec<InputSet> {
+    if let Some(path) = &opts.input {
+        return vec![InputSet::file(path)];
+    }
+    let dir = opts
+        .input_dir
+        .clone()
+        .or(env_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    if opts.input_sets.is_empty() {
+        return vec![InputSet::dir(dir)];
+    }
+    opts.input_sets
+        .iter()
+        .map(|name| InputSet {
+            name: Some(name.clone()),
+            source: Source::Dir(dir.join(name)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let mut opts = Options::default();
+        assert_eq!(resolve_with(&opts, None), vec![InputSet::dir(".")]);
+        assert_eq!(
+            resolve_with(&opts, Some("env".into()))[0].path(2),
+            Some(PathBuf::from("env/input02.txt"))
+        );
+
+        opts.input_dir = Some("inputs".into());
+        assert_eq!(
+            resolve_with(&opts, Some("env".into()))[0].path(11),
+            Some(PathBuf::from("inputs/input11.txt"))
+        );
+
+        opts.input_sets = vec!["alice".to_string(), "bob".to_string()];
+        let sets = resolve_with(&opts, None);
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[1].name.as_deref(), Some("bob"));
+        assert_eq!(sets[1].path(3), Some(PathBuf::from("inputs/bob/input03.txt")));
+        assert_eq!(
+            sets[0].answers_path(Path::new("answers.toml")),
+            PathBuf::from("inputs/alice/answers.toml")
+        );
+
+        opts.input = Some("day6.txt".into());
+        let sets = resolve_with(&opts, None);
+        assert_eq!(sets, vec![InputSet::file("day6.txt")]);
+        assert_eq!(sets[0].describe(6), "day6.txt");
+    }
+
+    #[test]
+    fn test_reader() {
+        let lines = read_lines_from("R 4\r\nU 4\nL 3\n".as_bytes()).unwrap();
+        assert_eq!(lines, vec!["R 4", "U 4", "L 3"]);
+
+        let set = InputSet::from_reader("generated", "1000\n2000\n".as_bytes()).unwrap();
+        assert_eq!(set.path(1), None);
+        assert_eq!(set.describe(1), "generated");
+        assert_eq!(set.load(1).unwrap(), vec!["1000", "2000"]);
+        assert_eq!(set.load(2).unwrap(), vec!["1000", "2000"]);
+    }
+}
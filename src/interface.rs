@@ -1 +1,181 @@
+use std::any::Any;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
 pub use adventools::prelude::*;
+
+pub use crate::cancel::check_cancelled;
+pub use crate::parse::{parse_each, parse_field, ParseError};
+
+/// The solution to one part of a day's puzzle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    /// A multi-line picture, such as the CRT output of day 10.
+    Picture(Vec<String>),
+}
+
+impl Answer {
+    pub fn is_multiline(&self) -> bool {
+        matches!(self, Answer::Picture(_))
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Picture(lines) => write!(f, "{}", lines.join("\n")),
+        }
+    }
+}
+
+macro_rules! answer_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    Answer::Number(n as i64)
+                }
+            }
+        )*
+    };
+}
+
+answer_from_int!(i32, i64, u8, u32, u64, usize);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+impl From<Vec<String>> for Answer {
+    fn from(lines: Vec<String>) -> Self {
+        Answer::Picture(lines)
+    }
+}
+
+/// Parsed input as handed around by the runner; see [`DayParsed::Parsed`].
+pub type AnyParsed = Arc<dyn Any + Send + Sync>;
+
+/// The object-safe view of a day that the runner drives. Implement
+/// [`DayParsed`] rather than this trait directly. Days are shared between
+/// threads when the runner works in parallel.
+pub trait Day: Send + Sync {
+    fn number(&self) -> u8;
+
+    /// Slow days only run when the runner is given `--include-slow`.
+    fn slow(&self) -> bool;
+
+    /// How long parsing and each part may take before the runner gives up.
+    fn budget(&self) -> Option<Duration>;
+
+    fn parse(&self, input: &[String]) -> Result<AnyParsed>;
+    fn part01(&self, parsed: &(dyn Any + Send + Sync)) -> Result<Answer>;
+    fn part02(&self, parsed: &(dyn Any + Send + Sync)) -> Result<Answer>;
+}
+
+/// Parses every line with `FromStr`, reporting the line number on failure.
+pub fn parse_lines<T>(lines: &[String]) -> Result<Vec<T>>
+where
+    T: FromStr<Err = ParseError>,
+{
+    Ok(parse_each(lines, |s| s.parse())?)
+}
+
+/// A day whose input is parsed once into `Parsed`, which is then shared by
+/// both parts.
+pub trait DayParsed: Send + Sync {
+    type Parsed: Send + Sync + 'static;
+
+    fn number(&self) -> u8;
+
+    fn slow(&self) -> bool {
+        false
+    }
+
+    fn budget(&self) -> Option<Duration> {
+        None
+    }
+
+    fn parse(&self, input: &[String]) -> Result<Self::Parsed>;
+    fn part01(&self, parsed: &Self::Parsed) -> Result<Answer>;
+    fn part02(&self, parsed: &Self::Parsed) -> Result<Answer>;
+}
+
+fn downcast<'a, T: DayParsed>(
+    day: &T,
+    parsed: &'a (dyn Any + Send + Sync),
+) -> Result<&'a T::Parsed> {
+    parsed.downcast_ref().ok_or_else(|| {
+        anyhow!(
+            "Day {:02} was given input parsed by another day",
+            DayParsed::number(day)
+        )
+    })
+}
+
+impl<T: DayParsed> Day for T {
+    fn number(&self) -> u8 {
+        DayParsed::number(self)
+    }
+
+    fn slow(&self) -> bool {
+        DayParsed::slow(self)
+    }
+
+    fn budget(&self) -> Option<Duration> {
+        DayParsed::budget(self)
+    }
+
+    fn parse(&self, input: &[String]) -> Result<AnyParsed> {
+        Ok(Arc::new(DayParsed::parse(self, input)?))
+    }
+
+    fn part01(&self, parsed: &(dyn Any + Send + Sync)) -> Result<Answer> {
+        DayParsed::part01(self, downcast(self, parsed)?)
+    }
+
+    fn part02(&self, parsed: &(dyn Any + Send + Sync)) -> Result<Answer> {
+        DayParsed::part02(self, downcast(self, parsed)?)
+    }
+}
+
+/// Defines the `register` function that `build.rs` expects every day
+/// module to provide.
+macro_rules! register_day {
+    ($day:ident) => {
+        pub fn register() -> std::sync::Arc<dyn $crate::interface::Day> {
+            std::sync::Arc::new($day {})
+        }
+    };
+}
+pub(crate) use register_day;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Answer::from(24000).to_string(), "24000");
+        assert_eq!(Answer::from(56000011_i64).to_string(), "56000011");
+        assert_eq!(Answer::from("CMZ").to_string(), "CMZ");
+        let picture = Answer::from(vec!["#.".to_string(), ".#".to_string()]);
+        assert!(picture.is_multiline());
+        assert_eq!(picture.to_string(), "#.\n.#");
+    }
+}
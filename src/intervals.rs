@@ -0,0 +1,214 @@
+//! Sets of integers stored as runs, for puzzles about sections, ranges and
+//! coverage where listing every member would be too slow.
+
+use std::ops::RangeInclusive;
+
+/// A set of integers kept as sorted, disjoint closed intervals. Intervals
+/// that touch are merged, so `1..=3` and `4..=6` are stored as `1..=6`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    runs: Vec<(i64, i64)>,
+}
+
+/// Sorts `runs` and merges any that overlap or touch, dropping empty ones.
+fn normalize(mut runs: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    runs.retain(|(lo, hi)| lo <= hi);
+    runs.sort_unstable();
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(runs.len());
+    for (lo, hi) in runs {
+        match merged.last_mut() {
+            Some(last) if lo <= last.1.saturating_add(1) => last.1 = last.1.max(hi),
+            _ => merged.push((lo, hi)),
+        }
+    }
+    merged
+}
+
+impl IntervalSet {
+    pub fn new() -> IntervalSet {
+        IntervalSet::default()
+    }
+
+    pub fn insert(&mut self, range: RangeInclusive<i64>) {
+        let mut runs = std::mem::take(&mut self.runs);
+        runs.push(range.into_inner());
+        self.runs = normalize(runs);
+    }
+
+    /// Adds every member of `other`.
+    pub fn merge(&mut self, other: &IntervalSet) {
+        let mut runs = std::mem::take(&mut self.runs);
+        runs.extend_from_slice(&other.runs);
+        self.runs = normalize(runs);
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut union = self.clone();
+        union.merge(other);
+        union
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut runs = vec![];
+        let (mut i, mut j) = (0, 0);
+        while let (Some(&(a, b)), Some(&(c, d))) = (self.runs.get(i), other.runs.get(j)) {
+            let (lo, hi) = (a.max(c), b.min(d));
+            if lo <= hi {
+                runs.push((lo, hi));
+            }
+            // drop whichever run ends first; it can't meet anything further on
+            if b < d {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { runs }
+    }
+
+    /// The members of this set that aren't in `other`.
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut runs = vec![];
+        let mut j = 0;
+        for &(a, b) in &self.runs {
+            while other.runs.get(j).is_some_and(|&(_, d)| d < a) {
+                j += 1;
+            }
+            // wide enough to step past i64::MAX
+            let mut start = a as i128;
+            for &(c, d) in other.runs[j..].iter().take_while(|&&(c, _)| c <= b) {
+                if c as i128 > start {
+                    runs.push((start as i64, c - 1));
+                }
+                start = start.max(d as i128 + 1);
+            }
+            if start <= b as i128 {
+                runs.push((start as i64, b));
+            }
+        }
+        IntervalSet { runs }
+    }
+
+    /// The parts of `within` that this set leaves uncovered.
+    pub fn gaps(&self, within: RangeInclusive<i64>) -> IntervalSet {
+        IntervalSet::from(within).difference(self)
+    }
+
+    pub fn contains(&self, n: i64) -> bool {
+        let idx = self.runs.partition_point(|&(_, hi)| hi < n);
+        self.runs.get(idx).is_some_and(|&(lo, _)| lo <= n)
+    }
+
+    /// Whether every member of `other` is also in this set.
+    pub fn is_superset(&self, other: &IntervalSet) -> bool {
+        other.difference(self).is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// How many integers the set holds.
+    pub fn len(&self) -> u64 {
+        self.runs
+            .iter()
+            .map(|&(lo, hi)| hi.abs_diff(lo).saturating_add(1))
+            .fold(0, u64::saturating_add)
+    }
+
+    /// The runs making up the set, in order.
+    pub fn ranges(&self) -> impl Iterator<Item = RangeInclusive<i64>> + '_ {
+        self.runs.iter().map(|&(lo, hi)| lo..=hi)
+    }
+}
+
+impl From<RangeInclusive<i64>> for IntervalSet {
+    fn from(range: RangeInclusive<i64>) -> IntervalSet {
+        IntervalSet {
+            runs: normalize(vec![range.into_inner()]),
+        }
+    }
+}
+
+impl FromIterator<RangeInclusive<i64>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<i64>>>(ranges: I) -> IntervalSet {
+        IntervalSet {
+            runs: normalize(ranges.into_iter().map(RangeInclusive::into_inner).collect()),
+        }
+    }
+}
+
+impl Extend<RangeInclusive<i64>> for IntervalSet {
+    fn extend<I: IntoIterator<Item = RangeInclusive<i64>>>(&mut self, ranges: I) {
+        let mut runs = std::mem::take(&mut self.runs);
+        runs.extend(ranges.into_iter().map(RangeInclusive::into_inner));
+        self.runs = normalize(runs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use proptest::prelude::*;
+
+    fn set(ranges: &[RangeInclusive<i64>]) -> IntervalSet {
+        ranges.iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut s = IntervalSet::new();
+        s.insert(1..=3);
+        s.insert(10..=12);
+        s.insert(4..=5);
+        // an empty range, built from variables so clippy lets it through
+        let (lo, hi) = (7, 6);
+        s.insert(lo..=hi);
+        assert_eq!(s.ranges().collect::<Vec<_>>(), vec![1..=5, 10..=12]);
+        assert_eq!(s.len(), 8);
+        assert!(s.contains(5) && s.contains(10));
+        assert!(!s.contains(6) && !s.contains(0) && !s.contains(13));
+        s.insert(0..=20);
+        assert_eq!(s, set(&[0..=20]));
+    }
+
+    #[test]
+    fn test_operations() {
+        let a = set(&[0..=9, 20..=29]);
+        let b = set(&[5..=24]);
+        assert_eq!(a.union(&b), set(&[0..=29]));
+        assert_eq!(a.intersection(&b), set(&[5..=9, 20..=24]));
+        assert_eq!(a.difference(&b), set(&[0..=4, 25..=29]));
+        assert_eq!(b.difference(&a), set(&[10..=19]));
+        assert_eq!(a.gaps(-5..=35), set(&[-5..=-1, 10..=19, 30..=35]));
+        assert!(a.is_superset(&set(&[2..=4, 21..=21])));
+        assert!(!a.is_superset(&b));
+        assert!(IntervalSet::new().intersection(&a).is_empty());
+        assert_eq!(set(&[i64::MIN..=i64::MAX]).len(), u64::MAX);
+    }
+
+    fn model(s: &IntervalSet) -> BTreeSet<i64> {
+        s.ranges().flatten().collect()
+    }
+
+    fn sets() -> impl Strategy<Value = IntervalSet> {
+        prop::collection::vec((-30i64..30, 0i64..8), 0..6)
+            .prop_map(|runs| runs.into_iter().map(|(lo, len)| lo..=lo + len).collect())
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_model(a in sets(), b in sets()) {
+            let (ma, mb) = (model(&a), model(&b));
+            prop_assert_eq!(model(&a.union(&b)), &ma | &mb);
+            prop_assert_eq!(model(&a.intersection(&b)), &ma & &mb);
+            prop_assert_eq!(model(&a.difference(&b)), &ma - &mb);
+            prop_assert_eq!(a.len(), ma.len() as u64);
+            for n in -40..40 {
+                prop_assert_eq!(a.contains(n), ma.contains(&n));
+            }
+        }
+    }
+}
@@ -1,3 +1,27 @@
-mod interface;
+pub mod interface;
 
+pub mod answers;
+pub mod bench;
+pub mod cancel;
+pub mod cli;
+pub mod client;
+pub mod dashboard;
 pub mod days;
+pub mod fixtures;
+pub mod frames;
+pub mod geometry;
+pub mod grid;
+pub mod input;
+pub mod intervals;
+pub mod memory;
+pub mod parse;
+pub mod report;
+pub mod runner;
+pub mod scaffold;
+pub mod search;
+pub mod verify;
+pub mod watch;
+
+#[cfg(feature = "alloc-stats")]
+#[global_allocator]
+static ALLOCATOR: memory::Counting = memory::Counting;
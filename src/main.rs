@@ -1,7 +1,197 @@
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use advent2022::answers::Answers;
+use advent2022::cli::{Command, Options, USAGE};
 use advent2022::days::all_days;
-use adventools::runner;
-use anyhow::Result;
+use advent2022::input::{self, InputSet};
+use advent2022::interface::*;
+use advent2022::report::Report;
+use advent2022::client::{self, Client, Submission};
+use advent2022::{bench, dashboard, frames, runner, scaffold, verify, watch};
+
+const YEAR: u16 = 2022;
+
+fn run_verify(days: &[Arc<dyn Day>], inputs: &InputSet, opts: &Options) -> Result<usize> {
+    let path = inputs.answers_path(&opts.answers);
+    let mut answers = Answers::load(&path)?;
+    let failures = verify::run(days, inputs, &mut answers, opts);
+    if opts.record {
+        answers.save(&path)?;
+    }
+    Ok(failures)
+}
+
+/// Solves the one selected part and submits the answer, unless the registry
+/// already knows the right one. A correct answer is recorded.
+fn run_submit(year: u16, day: &Arc<dyn Day>, inputs: &InputSet, opts: &Options) -> Result<usize> {
+    let path = inputs.answers_path(&opts.answers);
+    let mut answers = Answers::load(&path)?;
+    let result = runner::run_day(day, inputs, opts);
+    let Some(part) = result.parts.into_iter().next() else {
+        return Ok(0);
+    };
+    let answer = part.answer?;
+    if let Some(known) = answers.get(result.day, part.part) {
+        println!(
+            "Day {:02} part {}: {} already solved with {}, not submitting",
+            result.day, part.part, answer, known
+        );
+        return Ok(usize::from(answer.to_string() != known));
+    }
+    let client = Client::from_env(opts.base_url.as_deref())?;
+    let verdict = client.submit(year, result.day, part.part, &answer.to_string())?;
+    println!("Day {:02} part {}: {} {}", result.day, part.part, answer, verdict);
+    if verdict != Submission::Correct {
+        return Ok(1);
+    }
+    answers.insert(result.day, part.part, &answer);
+    answers.save(&path)?;
+    Ok(0)
+}
+
+fn run_set(
+    year: u16,
+    days: &[Arc<dyn Day>],
+    inputs: &InputSet,
+    opts: &Options,
+    report: &mut Report,
+) -> Result<usize> {
+    if opts.submit {
+        run_submit(year, &days[0], inputs, opts)
+    } else if opts.bench {
+        Ok(bench::run(days, inputs, opts))
+    } else if opts.verify || opts.record {
+        run_verify(days, inputs, opts)
+    } else {
+        Ok(runner::run(year, days, inputs, opts, report))
+    }
+}
 
-fn main() -> Result<()> {
-    runner::run(2022, all_days())
+fn main() -> ExitCode {
+    let opts = match Options::parse(std::env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if opts.help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    if let Command::NewDay(day) = opts.command {
+        return match scaffold::new_day(Path::new(env!("CARGO_MANIFEST_DIR")), day) {
+            Ok(created) => {
+                for path in created {
+                    println!("Created {}", path.display());
+                }
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    let year = opts.year.unwrap_or(YEAR);
+    if year != YEAR {
+        eprintln!("Only {} is available, not {}", YEAR, year);
+        return ExitCode::FAILURE;
+    }
+    let registered = all_days();
+    let days: Vec<_> = registered
+        .iter()
+        .filter(|d| opts.selects_day(d.as_ref()))
+        .cloned()
+        .collect();
+    if days.is_empty() {
+        eprintln!("No registered days match the selection (slow days need --include-slow)");
+        return ExitCode::FAILURE;
+    }
+    if opts.input.is_some() && days.len() != 1 {
+        eprintln!("--input needs exactly one day to be selected");
+        return ExitCode::from(2);
+    }
+    let sets = match input::resolve(&opts) {
+        Ok(sets) => sets,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if opts.fetch {
+        let fetched = Client::from_env(opts.base_url.as_deref())
+            .and_then(|client| client::fill_missing(&client, year, &days, &sets[0]));
+        match fetched {
+            Ok(paths) => {
+                for path in paths {
+                    println!("Fetched {}", path.display());
+                }
+            }
+            Err(e) => {
+                eprintln!("{:#}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    if opts.tui {
+        if sets.len() != 1 {
+            eprintln!("--tui works with a single input set");
+            return ExitCode::from(2);
+        }
+        return match dashboard::run(&registered, &sets[0], &opts) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{:#}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    if opts.watch {
+        if sets.len() != 1 {
+            eprintln!("--watch works with a single input set");
+            return ExitCode::from(2);
+        }
+        watch::run(&days, &sets[0], &opts);
+        return ExitCode::SUCCESS;
+    }
+    if let Some(target) = &opts.frames {
+        match target.open() {
+            Ok(sink) => frames::install(frames::Hook::new(sink, opts.frame_every)),
+            Err(e) => {
+                eprintln!("{:#}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let mut failures = 0;
+    let mut report = Report::default();
+    for inputs in sets {
+        if let Some(name) = &inputs.name {
+            println!("== Input set {} ==", name);
+        }
+        match run_set(year, &days, &inputs, &opts, &mut report) {
+            Ok(n) => failures += n,
+            Err(e) => {
+                eprintln!("{:#}", e);
+                failures += 1;
+            }
+        }
+    }
+    if let Err(e) = frames::finish() {
+        eprintln!("Writing frames: {:#}", e);
+        failures += 1;
+    }
+    if let Some(path) = &opts.report {
+        if let Err(e) = report.write(path) {
+            eprintln!("{:#}", e);
+            failures += 1;
+        }
+    }
+    match failures {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    }
 }
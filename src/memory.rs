@@ -0,0 +1,192 @@
+//! Heap accounting for the runner. Building with `--features alloc-stats`
+//! installs [`Counting`] as the global allocator, and the runner then
+//! reports the allocations each part made. Counts are kept per thread, so
+//! days running in parallel don't see each other's allocations.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt;
+
+use serde::Serialize;
+
+/// Whether allocations are being counted at all.
+pub const ENABLED: bool = cfg!(feature = "alloc-stats");
+
+/// What a piece of work did to the heap.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AllocStats {
+    /// Allocations made, counting every reallocation as one.
+    pub allocations: u64,
+    /// Bytes requested over all those allocations.
+    pub bytes: u64,
+    /// The most heap held at once, over what was held when counting began.
+    pub peak: u64,
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+impl fmt::Display for AllocStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} allocations, {} allocated, {} peak",
+            self.allocations,
+            format_bytes(self.bytes),
+            format_bytes(self.peak)
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Counts {
+    allocations: u64,
+    bytes: u64,
+    /// Can go negative when memory from before counting began is freed.
+    current: i64,
+    peak: i64,
+}
+
+impl Counts {
+    const ZERO: Counts = Counts {
+        allocations: 0,
+        bytes: 0,
+        current: 0,
+        peak: 0,
+    };
+}
+
+thread_local! {
+    static COUNTS: Cell<Counts> = const { Cell::new(Counts::ZERO) };
+}
+
+fn update(f: impl FnOnce(&mut Counts)) {
+    // Unavailable while the thread shuts down; those allocations go uncounted.
+    let _ = COUNTS.try_with(|cell| {
+        let mut counts = cell.get();
+        f(&mut counts);
+        cell.set(counts);
+    });
+}
+
+fn grow(size: usize) {
+    update(|counts| {
+        counts.allocations += 1;
+        counts.bytes += size as u64;
+        counts.current += size as i64;
+        counts.peak = counts.peak.max(counts.current);
+    });
+}
+
+fn shrink(size: usize) {
+    update(|counts| counts.current -= size as i64);
+}
+
+/// The system allocator, counting what passes through it.
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            shrink(layout.size());
+            grow(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Runs `f` and reports the allocations it made on this thread, or `None`
+/// when allocations aren't being counted. Calls may be nested.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocStats>) {
+    if !ENABLED {
+        return (f(), None);
+    }
+    let outer = COUNTS.with(|cell| cell.replace(Counts::ZERO));
+    let result = f();
+    let inner = COUNTS.with(|cell| {
+        let inner = cell.get();
+        cell.set(Counts {
+            allocations: outer.allocations + inner.allocations,
+            bytes: outer.bytes + inner.bytes,
+            current: outer.current + inner.current,
+            peak: outer.peak.max(outer.current + inner.peak),
+        });
+        inner
+    });
+    let stats = AllocStats {
+        allocations: inner.allocations,
+        bytes: inner.bytes,
+        peak: inner.peak.max(0) as u64,
+    };
+    (result, Some(stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let stats = AllocStats {
+            allocations: 3,
+            bytes: 5 * 1024 * 1024 + 512 * 1024,
+            peak: 100,
+        };
+        assert_eq!(stats.to_string(), "3 allocations, 5.5 MiB allocated, 100 B peak");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+    }
+
+    #[test]
+    fn test_measure() {
+        let (outer, outer_stats) = measure(|| {
+            let (len, inner_stats) = measure(|| vec![0u64; 1000].len());
+            let kept = vec![0u8; 100];
+            (len, inner_stats, kept)
+        });
+        assert_eq!(outer.0, 1000);
+        if !ENABLED {
+            assert_eq!((outer.1, outer_stats), (None, None));
+            return;
+        }
+        let inner = outer.1.unwrap();
+        assert_eq!(inner.allocations, 1);
+        assert_eq!((inner.bytes, inner.peak), (8000, 8000));
+        let outer_stats = outer_stats.unwrap();
+        assert_eq!(outer_stats.allocations, 2);
+        assert_eq!(outer_stats.bytes, 8100);
+        assert_eq!(outer_stats.peak, 8000);
+    }
+}
@@ -0,0 +1,369 @@
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use nom::character::complete::space0;
+use nom::error::{ErrorKind, FromExternalError, ParseError as NomParseError};
+use nom::sequence::{pair, preceded, tuple};
+use nom::IResult;
+
+/// How much of the offending text to quote in an error.
+const FOUND_LEN: usize = 20;
+
+/// A malformed piece of puzzle input, pinpointed to a line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line within the input. Parsers that only see one line leave
+    /// this for their caller to fill in with [`ParseError::at_line`].
+    pub line: Option<usize>,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+    /// What the parser was looking for, e.g. "an integer".
+    pub expected: String,
+    /// The text at the error position, or "end of line".
+    pub found: String,
+}
+
+impl ParseError {
+    /// An error at byte `offset` of `text`.
+    pub fn new(expected: impl Into<String>, text: &str, offset: usize) -> ParseError {
+        let column = text.char_indices().take_while(|&(i, _)| i < offset).count() + 1;
+        let rest: Vec<char> = text
+            .char_indices()
+            .filter(|&(i, _)| i >= offset)
+            .map(|(_, c)| c)
+            .take(FOUND_LEN + 1)
+            .collect();
+        let found = if rest.is_empty() {
+            "end of line".to_string()
+        } else if rest.len() > FOUND_LEN {
+            format!("`{}…`", rest[..FOUND_LEN].iter().collect::<String>())
+        } else {
+            format!("`{}`", rest.iter().collect::<String>())
+        };
+        ParseError {
+            line: None,
+            column,
+            expected: expected.into(),
+            found,
+        }
+    }
+
+    /// Records which line the error is on, unless that is already known.
+    pub fn at_line(mut self, line: usize) -> ParseError {
+        self.line.get_or_insert(line);
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}, ", line)?;
+        }
+        write!(
+            f,
+            "column {}: expected {}, found {}",
+            self.column, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses `text[range]`, e.g. one field of a line, reporting failure at the
+/// start of the range.
+pub fn parse_field<T: FromStr>(
+    text: &str,
+    range: Range<usize>,
+    expected: &str,
+) -> Result<T, ParseError> {
+    text[range.clone()]
+        .parse()
+        .map_err(|_| ParseError::new(expected, text, range.start))
+}
+
+/// Parses each line with `f`, tagging errors with their 1-based line number.
+pub fn parse_each<T>(
+    lines: &[String],
+    mut f: impl FnMut(&str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| f(line).map_err(|e| e.at_line(i + 1)))
+        .collect()
+}
+
+/// A nom error remembering what the parser wanted where it failed, so it
+/// can become a [`ParseError`] at the right column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expected<'a> {
+    /// The input left when the failing parser started.
+    pub input: &'a str,
+    pub what: String,
+}
+
+impl<'a> Expected<'a> {
+    pub fn new(input: &'a str, what: impl Into<String>) -> Expected<'a> {
+        Expected {
+            input,
+            what: what.into(),
+        }
+    }
+}
+
+impl<'a> NomParseError<&'a str> for Expected<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Expected<'a> {
+        let what = match kind {
+            ErrorKind::Digit => "a digit",
+            ErrorKind::Space | ErrorKind::MultiSpace => "a space",
+            ErrorKind::Eof => "end of line",
+            _ => "well-formed input",
+        };
+        Expected::new(input, what)
+    }
+
+    fn append(_: &'a str, _: ErrorKind, other: Expected<'a>) -> Expected<'a> {
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Expected<'a> {
+        Expected::new(input, format!("`{}`", c))
+    }
+
+    /// Of two failed alternatives, reports the one that got further.
+    fn or(self, other: Expected<'a>) -> Expected<'a> {
+        if other.input.len() < self.input.len() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl<'a, E> FromExternalError<&'a str, E> for Expected<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _: E) -> Expected<'a> {
+        Expected::from_error_kind(input, kind)
+    }
+}
+
+pub type PResult<'a, T> = IResult<&'a str, T, Expected<'a>>;
+
+/// An error that stops parsing at `input`, for checks on values that
+/// parsed but can't be right, such as a stack that doesn't exist.
+pub fn failure<'a>(input: &'a str, what: impl Into<String>) -> nom::Err<Expected<'a>> {
+    nom::Err::Failure(Expected::new(input, what))
+}
+
+/// Runs `parser` over the whole of `line`.
+pub fn parse_line<'a, T>(
+    line: &'a str,
+    mut parser: impl FnMut(&'a str) -> PResult<'a, T>,
+) -> Result<T, ParseError> {
+    let offset = |rest: &str| line.len() - rest.len();
+    match parser(line) {
+        Ok(("", value)) => Ok(value),
+        Ok((rest, _)) => Err(ParseError::new("end of line", line, offset(rest))),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+            Err(ParseError::new(e.what, line, offset(e.input)))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(ParseError::new("more input", line, line.len())),
+    }
+}
+
+/// Describes a failure of `parser` as `what`, unless it got some way in
+/// before failing and so knows better what went wrong.
+pub fn expect<'a, T>(
+    what: &'static str,
+    mut parser: impl FnMut(&'a str) -> PResult<'a, T>,
+) -> impl FnMut(&'a str) -> PResult<'a, T> {
+    move |input: &'a str| {
+        parser(input).map_err(|err| {
+            err.map(|e| if e.input.len() == input.len() { Expected::new(input, what) } else { e })
+        })
+    }
+}
+
+/// Exactly `text`.
+pub fn lit<'a>(text: &'static str) -> impl FnMut(&'a str) -> PResult<'a, &'a str> {
+    move |input: &'a str| match input.strip_prefix(text) {
+        Some(rest) => Ok((rest, &input[..text.len()])),
+        None => Err(nom::Err::Error(Expected::new(input, format!("`{}`", text)))),
+    }
+}
+
+/// An integer, optionally negative, reported as `what` if it is missing
+/// or doesn't fit in `T`.
+pub fn number<'a, T: FromStr>(what: &'static str) -> impl FnMut(&'a str) -> PResult<'a, T> {
+    move |input: &'a str| {
+        let unsigned = input.strip_prefix('-').unwrap_or(input);
+        let digits = unsigned.bytes().take_while(u8::is_ascii_digit).count();
+        let len = input.len() - unsigned.len() + digits;
+        match input[..len].parse() {
+            Ok(n) if digits > 0 => Ok((&input[len..], n)),
+            _ => Err(nom::Err::Error(Expected::new(input, what))),
+        }
+    }
+}
+
+/// A run of letters, digits and underscores, such as a name.
+pub fn word<'a>(what: &'static str) -> impl FnMut(&'a str) -> PResult<'a, &'a str> {
+    move |input: &'a str| {
+        let len = input
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(input.len());
+        match len {
+            0 => Err(nom::Err::Error(Expected::new(input, what))),
+            _ => Ok((&input[len..], &input[..len])),
+        }
+    }
+}
+
+fn separated_by<'a, T>(
+    mut sep: impl FnMut(&'a str) -> Option<&'a str>,
+    mut item: impl FnMut(&'a str) -> PResult<'a, T>,
+) -> impl FnMut(&'a str) -> PResult<'a, Vec<T>> {
+    move |input: &'a str| {
+        let (mut rest, first) = item(input)?;
+        let mut items = vec![first];
+        while let Some(next) = sep(rest) {
+            // a separator promises another item, so its absence is an error
+            let (after, next_item) = item(next)?;
+            items.push(next_item);
+            rest = after;
+        }
+        Ok((rest, items))
+    }
+}
+
+/// One or more `item`s with `sep` between each.
+pub fn separated<'a, T>(
+    sep: &'static str,
+    item: impl FnMut(&'a str) -> PResult<'a, T>,
+) -> impl FnMut(&'a str) -> PResult<'a, Vec<T>> {
+    separated_by(move |s: &'a str| s.strip_prefix(sep), item)
+}
+
+/// One or more `item`s separated by commas, each optionally followed by
+/// spaces.
+pub fn comma_list<'a, T>(
+    item: impl FnMut(&'a str) -> PResult<'a, T>,
+) -> impl FnMut(&'a str) -> PResult<'a, Vec<T>> {
+    separated_by(|s: &'a str| s.strip_prefix(',').map(|s| s.trim_start_matches(' ')), item)
+}
+
+/// `key=value`, giving the value.
+pub fn key_value<'a, T>(
+    key: &'static str,
+    value: impl FnMut(&'a str) -> PResult<'a, T>,
+) -> impl FnMut(&'a str) -> PResult<'a, T> {
+    preceded(pair(lit(key), lit("=")), value)
+}
+
+/// A line such as `  Test: divisible by 23`: optional indentation, then
+/// `label`, a colon and a space, then the value.
+pub fn labelled<'a, T>(
+    label: &'static str,
+    value: impl FnMut(&'a str) -> PResult<'a, T>,
+) -> impl FnMut(&'a str) -> PResult<'a, T> {
+    preceded(tuple((space0, lit(label), lit(": "))), value)
+}
+
+/// Splits `lines` at blank lines, giving each block of lines with the
+/// index of its first line. Runs of blank lines count as one break.
+pub fn blocks<S: AsRef<str>>(lines: &[S]) -> impl Iterator<Item = (usize, &[S])> {
+    let mut start = 0;
+    lines
+        .split(|line| line.as_ref().is_empty())
+        .map(move |block| {
+            let first = start;
+            start += block.len() + 1;
+            (first, block)
+        })
+        .filter(|(_, block)| !block.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let e = ParseError::new("`,`", "2-4;6-8", 3);
+        assert_eq!(e.column, 4);
+        assert_eq!(e.to_string(), "column 4: expected `,`, found `;6-8`");
+        let e = e.at_line(7).at_line(9);
+        assert_eq!(e.to_string(), "line 7, column 4: expected `,`, found `;6-8`");
+
+        let e = ParseError::new("an integer", "x=", 2);
+        assert_eq!(e.found, "end of line");
+        let e = ParseError::new("a digit", "é0123456789012345678901234", 0);
+        assert_eq!(e.found, "`é0123456789012345678…`");
+        // offsets inside a multi-byte character count as that character
+        assert_eq!(ParseError::new("x", "aéb", 2).column, 3);
+    }
+
+    #[test]
+    fn test_parse_each() {
+        let lines = vec!["1".to_string(), "2".to_string(), "x3".to_string()];
+        let err = parse_each(&lines, |s| parse_field::<i32>(s, 0..s.len(), "an integer"))
+            .unwrap_err();
+        assert_eq!(err.line, Some(3));
+        assert_eq!(err.column, 1);
+        assert_eq!(err.found, "`x3`");
+        assert_eq!(
+            parse_each(&lines[..2], |s| parse_field::<i32>(s, 0..s.len(), "an integer")),
+            Ok(vec![1, 2])
+        );
+    }
+
+    fn coord(s: &str) -> PResult<'_, (i32, i32)> {
+        let (s, x) = key_value("x", number("a coordinate"))(s)?;
+        let (s, _) = lit(", ")(s)?;
+        let (s, y) = key_value("y", number("a coordinate"))(s)?;
+        Ok((s, (x, y)))
+    }
+
+    fn items(s: &str) -> PResult<'_, Vec<u32>> {
+        labelled("Starting items", comma_list(number("an item")))(s)
+    }
+
+    fn small(s: &str) -> PResult<'_, u8> {
+        let (rest, n) = expect("a small number", number("a number"))(s)?;
+        if n > 3 {
+            return Err(failure(s, "at most 3"));
+        }
+        Ok((rest, n))
+    }
+
+    #[test]
+    fn test_combinators() {
+        assert_eq!(parse_line("x=-2, y=15", coord), Ok((-2, 15)));
+        let err = parse_line("x=-2; y=15", coord).unwrap_err();
+        assert_eq!(err.to_string(), "column 5: expected `, `, found `; y=15`");
+        let err = parse_line("x=-, y=15", coord).unwrap_err();
+        assert_eq!(err.to_string(), "column 3: expected a coordinate, found `-, y=15`");
+        let err = parse_line("x=1, y=15 z", coord).unwrap_err();
+        assert_eq!(err.to_string(), "column 10: expected end of line, found ` z`");
+        assert!(parse_line("300", number::<u8>("a byte")).is_err());
+        assert!(parse_line("-3", number::<u8>("a byte")).is_err());
+
+        assert_eq!(parse_line("  Starting items: 79,98, 3", items), Ok(vec![79, 98, 3]));
+        assert_eq!(parse_line("  Starting items: 79, x", items).unwrap_err().column, 23);
+        let names = parse_line("AA -> b_2", separated(" -> ", word("a name")));
+        assert_eq!(names, Ok(vec!["AA", "b_2"]));
+
+        assert_eq!(parse_line("x", small).unwrap_err().expected, "a small number");
+        let err = parse_line("4 too", small).unwrap_err();
+        assert_eq!(err.to_string(), "column 1: expected at most 3, found `4 too`");
+    }
+
+    #[test]
+    fn test_blocks() {
+        let lines = ["a", "b", "", "c", "", "", "d", ""];
+        let found: Vec<_> = blocks(&lines).collect();
+        assert_eq!(found, vec![(0, &lines[0..2]), (3, &lines[3..4]), (6, &lines[6..7])]);
+        assert_eq!(blocks::<&str>(&[]).count(), 0);
+    }
+}
@@ -0,0 +1,148 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::input::InputSet;
+use crate::interface::*;
+use crate::memory::AllocStats;
+use crate::runner::DayResult;
+
+/// One line of the machine-readable report: the outcome of one part.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PartRecord {
+    pub year: u16,
+    pub input_set: Option<String>,
+    pub day: u8,
+    pub part: u8,
+    pub input: String,
+    pub success: bool,
+    pub answer: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: f64,
+    /// Only present when built with the `alloc-stats` feature.
+    pub memory: Option<AllocStats>,
+}
+
+/// Collects the results of a run for dashboards and regression scripts.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub records: Vec<PartRecord>,
+}
+
+impl Report {
+    pub fn add(&mut self, year: u16, inputs: &InputSet, result: &DayResult) {
+        for part in &result.parts {
+            let (answer, error) = match &part.answer {
+                Ok(answer) => (Some(answer.to_string()), None),
+                Err(e) => (None, Some(format!("{:#}", e))),
+            };
+            self.records.push(PartRecord {
+                year,
+                input_set: inputs.name.clone(),
+                day: result.day,
+                part: part.part,
+                input: inputs.describe(result.day),
+                success: error.is_none(),
+                answer,
+                error,
+                duration_ms: part.elapsed.as_nanos() as f64 / 1e6,
+                memory: part.memory,
+            });
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.records)?)
+    }
+
+    /// One JSON object per line.
+    pub fn to_json_lines(&self) -> Result<String> {
+        let mut out = String::new();
+        for record in &self.records {
+            out.push_str(&serde_json::to_string(record)?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// Writes the report as JSON lines if `path` ends in `.jsonl`, and as a
+    /// JSON array otherwise.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let contents = if path.extension().is_some_and(|ext| ext == "jsonl") {
+            self.to_json_lines()?
+        } else {
+            self.to_json()?
+        };
+        fs::write(path, contents).with_context(|| format!("Writing {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::runner::PartResult;
+
+    fn report() -> Report {
+        let mut report = Report::default();
+        let result = DayResult {
+            day: 5,
+            parts: vec![
+                PartResult {
+                    part: 1,
+                    answer: Ok("CMZ".into()),
+                    elapsed: Duration::from_micros(1500),
+                    memory: Some(AllocStats {
+                        allocations: 2,
+                        bytes: 64,
+                        peak: 48,
+                    }),
+                },
+                PartResult {
+                    part: 2,
+                    answer: Err(anyhow!("boom")),
+                    elapsed: Duration::ZERO,
+                    memory: None,
+                },
+            ],
+            elapsed: Duration::from_millis(2),
+        };
+        report.add(2022, &InputSet::dir("inputs"), &result);
+        report
+    }
+
+    #[test]
+    fn test_records() {
+        let report = report();
+        assert_eq!(report.records.len(), 2);
+        let first = &report.records[0];
+        assert_eq!(first.input, "inputs/input05.txt");
+        assert!(first.success);
+        assert_eq!(first.answer.as_deref(), Some("CMZ"));
+        assert_eq!(first.duration_ms, 1.5);
+        let second = &report.records[1];
+        assert!(!second.success);
+        assert_eq!(second.error.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let lines = report().to_json_lines().unwrap();
+        let parsed: Vec<serde_json::Value> = lines
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0]["day"], 5);
+        assert_eq!(parsed[0]["answer"], "CMZ");
+        assert_eq!(parsed[0]["memory"]["peak"], 48);
+        assert_eq!(parsed[1]["answer"], serde_json::Value::Null);
+        assert_eq!(parsed[1]["error"], "boom");
+        assert_eq!(parsed[1]["memory"], serde_json::Value::Null);
+    }
+}
@@ -0,0 +1,263 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+
+use crate::cancel::{with_budget, Timeout};
+use crate::cli::Options;
+use crate::input::{read_lines_from, InputSet};
+use crate::interface::*;
+use crate::memory::{measure, AllocStats};
+use crate::report::Report;
+
+pub struct PartResult {
+    pub part: u8,
+    pub answer: Result<Answer>,
+    pub elapsed: Duration,
+    /// What the part did to the heap, when built with `alloc-stats`.
+    pub memory: Option<AllocStats>,
+}
+
+/// Everything produced by running the selected parts of one day.
+pub struct DayResult {
+    pub day: u8,
+    pub parts: Vec<PartResult>,
+    /// Wall time for the whole day, including reading and parsing input.
+    pub elapsed: Duration,
+}
+
+fn report(day: u8, part: u8, answer: &Answer) {
+    if answer.is_multiline() {
+        println!("Day {:02} part {}:\n{}", day, part, answer);
+    } else {
+        println!("Day {:02} part {}: {}", day, part, answer);
+    }
+}
+
+pub fn solve(day: &dyn Day, part: u8, parsed: &(dyn Any + Send + Sync)) -> Result<Answer> {
+    match part {
+        1 => day.part01(parsed),
+        2 => day.part02(parsed),
+        _ => Err(anyhow!("Day {:02} has no part {}", day.number(), part)),
+    }
+}
+
+/// Solves one part of `day` with input read from `reader`, e.g. stdin or
+/// a byte slice in tests.
+pub fn solve_reader<R: Read>(day: &dyn Day, part: u8, reader: R) -> Result<Answer> {
+    let parsed = day.parse(&read_lines_from(reader)?)?;
+    solve(day, part, &*parsed)
+}
+
+pub fn selected_parts(opts: &Options) -> impl Iterator<Item = u8> + '_ {
+    [1, 2].into_iter().filter(|&p| opts.selects_part(p))
+}
+
+/// Runs the selected parts of one day. If the input can't be loaded, every
+/// selected part fails with that error. Parsing and each part are given
+/// `--timeout` or the day's own budget, and fail with [`Timeout`] if they
+/// take longer.
+pub fn run_day(day: &Arc<dyn Day>, inputs: &InputSet, opts: &Options) -> DayResult {
+    let start = Instant::now();
+    let budget = opts.timeout.or_else(|| day.budget());
+    let parsed = inputs.load(day.number()).and_then(|input| {
+        let day = Arc::clone(day);
+        with_budget(budget, move || day.parse(&input)).context("Parsing input")
+    });
+    let parts = match parsed {
+        Ok(parsed) => selected_parts(opts)
+            .map(|part| {
+                let part_start = Instant::now();
+                let (day, parsed) = (Arc::clone(day), Arc::clone(&parsed));
+                let measured = with_budget(budget, move || {
+                    Ok(measure(|| solve(day.as_ref(), part, &*parsed)))
+                });
+                let (answer, memory) = match measured {
+                    Ok((answer, memory)) => (answer, memory),
+                    Err(e) => (Err(e), None),
+                };
+                PartResult {
+                    part,
+                    answer,
+                    elapsed: part_start.elapsed(),
+                    memory,
+                }
+            })
+            .collect(),
+        Err(e) => selected_parts(opts)
+            .map(|part| PartResult {
+                part,
+                answer: Err(anyhow!("{:#}", e)),
+                elapsed: Duration::ZERO,
+                memory: None,
+            })
+            .collect(),
+    };
+    DayResult {
+        day: day.number(),
+        parts,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Runs every day, on `opts.threads()` worker threads if requested, and
+/// hands the results to `on_result` in the order the days were given.
+pub fn execute(
+    days: &[Arc<dyn Day>],
+    inputs: &InputSet,
+    opts: &Options,
+    mut on_result: impl FnMut(DayResult),
+) {
+    let Some(threads) = opts.threads() else {
+        for day in days {
+            on_result(run_day(day, inputs, opts));
+        }
+        return;
+    };
+
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|s| {
+        for _ in 0..threads.min(days.len()) {
+            let tx = tx.clone();
+            let next = &next;
+            s.spawn(move || loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some(day) = days.get(idx) else {
+                    break;
+                };
+                if tx.send((idx, run_day(day, inputs, opts))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        // Results arrive in completion order; hold them back until every
+        // earlier day has been reported.
+        let mut pending = BTreeMap::new();
+        let mut expected = 0;
+        for (idx, result) in rx {
+            pending.insert(idx, result);
+            while let Some(result) = pending.remove(&expected) {
+                on_result(result);
+                expected += 1;
+            }
+        }
+    });
+}
+
+fn print_result(result: &DayResult) -> usize {
+    let mut failures = 0;
+    for part in &result.parts {
+        match &part.answer {
+            Ok(answer) => {
+                report(result.day, part.part, answer);
+                if let Some(memory) = &part.memory {
+                    println!("Day {:02} part {} memory: {}", result.day, part.part, memory);
+                }
+            }
+            Err(e) if e.is::<Timeout>() => {
+                println!("Day {:02} part {}: {}", result.day, part.part, e);
+                failures += 1;
+            }
+            Err(e) => {
+                eprintln!("Day {:02} part {} failed: {:#}", result.day, part.part, e);
+                failures += 1;
+            }
+        }
+    }
+    println!("Day {:02} finished in {:.1?}", result.day, result.elapsed);
+    failures
+}
+
+/// Runs the selected parts of every day, adding their results to `report`
+/// and returning how many of them failed.
+pub fn run(
+    year: u16,
+    days: &[Arc<dyn Day>],
+    inputs: &InputSet,
+    opts: &Options,
+    report: &mut Report,
+) -> usize {
+    println!("Advent of Code {}", year);
+    let mut failures = 0;
+    execute(days, inputs, opts, |result| {
+        failures += print_result(&result);
+        report.add(year, inputs, &result);
+    });
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::day06;
+
+    #[test]
+    fn test_solve_reader() {
+        let day = day06::D {};
+        let input = "mjqjpqmgbljsphdztnvjfqwrcgsmlb\n";
+        assert_eq!(solve_reader(&day, 1, input.as_bytes()).unwrap(), Answer::Number(7));
+        assert_eq!(solve_reader(&day, 2, input.as_bytes()).unwrap(), Answer::Number(19));
+        assert!(solve_reader(&day, 3, input.as_bytes()).is_err());
+    }
+
+    /// Finishes in reverse order of its day number.
+    struct Sleepy(u8);
+
+    impl DayParsed for Sleepy {
+        type Parsed = ();
+
+        fn number(&self) -> u8 {
+            self.0
+        }
+        fn parse(&self, _input: &[String]) -> Result<()> {
+            Ok(())
+        }
+        fn part01(&self, _: &()) -> Result<Answer> {
+            thread::sleep(Duration::from_millis(40 - 10 * self.0 as u64));
+            Ok(self.0.into())
+        }
+        fn part02(&self, _: &()) -> Result<Answer> {
+            Err(anyhow!("no part 2"))
+        }
+    }
+
+    #[test]
+    fn test_execute_parallel_in_order() {
+        let days: Vec<Arc<dyn Day>> = (1..=3).map(|n| Arc::new(Sleepy(n)) as Arc<dyn Day>).collect();
+        let inputs = InputSet::lines("test", vec![]);
+        let opts = Options {
+            jobs: Some(3),
+            ..Options::default()
+        };
+        let mut order = vec![];
+        execute(&days, &inputs, &opts, |result| {
+            assert_eq!(result.parts[0].answer.as_ref().unwrap(), &Answer::from(result.day));
+            assert!(result.parts[1].answer.is_err());
+            order.push(result.day);
+        });
+        assert_eq!(order, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let days: Vec<Arc<dyn Day>> = vec![Arc::new(Sleepy(1))];
+        let inputs = InputSet::lines("test", vec![]);
+        let opts = Options {
+            part: Some(1),
+            timeout: Some(Duration::from_millis(5)),
+            ..Options::default()
+        };
+        let mut results = vec![];
+        execute(&days, &inputs, &opts, |result| results.push(result));
+        let err = results[0].parts[0].answer.as_ref().unwrap_err();
+        assert_eq!(err.downcast_ref::<Timeout>(), Some(&Timeout(Duration::from_millis(5))));
+    }
+}
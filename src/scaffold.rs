@@ -0,0 +1,146 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+
+use crate::fixtures;
+use crate::interface::*;
+
+/// Where day modules live, relative to the crate root.
+pub const DAYS_DIR: &str = "src/days";
+
+/// Skeleton for a new day; `__DAY__` is the day number. Its example answers
+/// are checked by the fixture tests once they are added to the fixture.
+const TEMPLATE: &str = r#"use crate::interface::*;
+use anyhow::anyhow;
+
+pub struct D {}
+
+register_day!(D);
+
+impl DayParsed for D {
+    type Parsed = Vec<String>;
+
+    fn number(&self) -> u8 {
+        __DAY__
+    }
+    fn parse(&self, input: &[String]) -> Result<Vec<String>> {
+        Ok(input.to_vec())
+    }
+    fn part01(&self, _input: &Vec<String>) -> Result<Answer> {
+        Err(anyhow!("Not solved yet"))
+    }
+    fn part02(&self, _input: &Vec<String>) -> Result<Answer> {
+        Err(anyhow!("Not solved yet"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn test_data() -> Vec<String> {
+        fixtures::load(__DAY__).unwrap().remove(0).input
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(DayParsed::parse(&D {}, &test_data()).is_ok());
+    }
+}
+"#;
+
+/// Starting point for a day's fixture; see [`fixtures::parse`].
+const FIXTURE_TEMPLATE: &str = "Paste the example input for day __DAY__ below, then add its answers
+as `--- part1 <answer>` and `--- part2 <answer>` lines.
+--- input
+";
+
+pub fn module_source(day: u8) -> String {
+    TEMPLATE.replace("__DAY__", &day.to_string())
+}
+
+fn write_new(path: &Path, contents: &str) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .with_context(|| format!("Creating {}", path.display()))?;
+    file.write_all(contents.as_bytes())
+        .with_context(|| format!("Writing {}", path.display()))
+}
+
+/// Creates the module for `day` under `root`, plus a fixture to hold its
+/// example unless one is already there. `build.rs` registers
+/// the module on the next build. Returns the files created.
+pub fn new_day(root: &Path, day: u8) -> Result<Vec<PathBuf>> {
+    if !(1..=25).contains(&day) {
+        bail!("Invalid day {}, days run from 1 to 25", day);
+    }
+    let days_dir = root.join(DAYS_DIR);
+    // build.rs reads `day7.rs` and `day07.rs` as the same day
+    for entry in fs::read_dir(&days_dir).with_context(|| format!("Reading {}", days_dir.display()))? {
+        let path = entry?.path();
+        let number = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.strip_prefix("day"))
+            .and_then(|n| n.parse::<u8>().ok());
+        if number == Some(day) && path.extension().is_some_and(|ext| ext == "rs") {
+            bail!("Day {} already exists in {}", day, path.display());
+        }
+    }
+
+    let mut created = vec![];
+    let module = days_dir.join(format!("day{:02}.rs", day));
+    write_new(&module, &module_source(day))?;
+    created.push(module);
+
+    let fixture = fixtures::path(root, day);
+    if !fixture.exists() {
+        fs::create_dir_all(root.join(fixtures::DIR))?;
+        write_new(&fixture, &FIXTURE_TEMPLATE.replace("__DAY__", &day.to_string()))?;
+        created.push(fixture);
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_day() {
+        let root = std::env::temp_dir().join(format!("advent-scaffold-{}", std::process::id()));
+        fs::create_dir_all(root.join(DAYS_DIR)).unwrap();
+        fs::write(root.join(DAYS_DIR).join("day7.rs"), "").unwrap();
+
+        let created = new_day(&root, 17).unwrap();
+        assert_eq!(
+            created,
+            vec![root.join("src/days/day17.rs"), root.join("fixtures/day17.txt")]
+        );
+        let source = fs::read_to_string(&created[0]).unwrap();
+        assert!(source.contains("register_day!(D);"));
+        assert!(source.contains("        17\n"));
+        assert!(source.contains("fixtures::load(17)"));
+        let fixture = fs::read_to_string(&created[1]).unwrap();
+        assert_eq!(fixtures::parse(&fixture).unwrap(), vec![fixtures::Example::default()]);
+
+        assert!(new_day(&root, 17).is_err());
+        assert!(new_day(&root, 7).is_err());
+        assert!(new_day(&root, 26).is_err());
+
+        // an existing fixture is kept
+        fs::write(root.join("fixtures/day03.txt"), "--- input\nvJrwpWtwJgWrhcsFMMfFFhFp\n").unwrap();
+        assert_eq!(new_day(&root, 3).unwrap(), vec![root.join("src/days/day03.rs")]);
+        assert_eq!(
+            fs::read_to_string(root.join("fixtures/day03.txt")).unwrap(),
+            "--- input\nvJrwpWtwJgWrhcsFMMfFFhFp\n"
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,259 @@
+//! Shortest-path searches over graphs given as a neighbour function, so a
+//! day only has to say where each node leads. Every search accepts several
+//! starting nodes at once and remembers how it reached each node, so a
+//! path can be read back from any node it visited.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+/// A route through the graph, starts first, with what it cost to follow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N> {
+    pub nodes: Vec<N>,
+    pub cost: u64,
+}
+
+impl<N> Path<N> {
+    /// How many edges the path takes.
+    pub fn steps(&self) -> usize {
+        self.nodes.len() - 1
+    }
+}
+
+/// Follows the predecessors recorded in `came_from` back from `end`.
+fn walk_back<N: Eq + Hash + Clone, C>(came_from: &HashMap<N, (Option<N>, C)>, end: &N) -> Option<Vec<N>> {
+    let mut nodes = vec![end.clone()];
+    let mut prev = &came_from.get(end)?.0;
+    while let Some(node) = prev {
+        nodes.push(node.clone());
+        prev = &came_from[node].0;
+    }
+    nodes.reverse();
+    Some(nodes)
+}
+
+/// A breadth-first search, expanded one layer at a time so callers can
+/// look at the frontier and everything visited between steps.
+pub struct Bfs<N, F> {
+    neighbors: F,
+    layer: Vec<N>,
+    depth: usize,
+    /// Each visited node's predecessor and depth.
+    visited: HashMap<N, (Option<N>, usize)>,
+}
+
+impl<N, F, I> Bfs<N, F>
+where
+    N: Eq + Hash + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    pub fn new(starts: impl IntoIterator<Item = N>, neighbors: F) -> Bfs<N, F> {
+        let mut visited = HashMap::new();
+        let mut layer = vec![];
+        for start in starts {
+            if visited.insert(start.clone(), (None, 0)).is_none() {
+                layer.push(start);
+            }
+        }
+        Bfs {
+            neighbors,
+            layer,
+            depth: 0,
+            visited,
+        }
+    }
+
+    /// How many steps the current layer is from the starts.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The nodes first reached at the current depth.
+    pub fn layer(&self) -> &[N] {
+        &self.layer
+    }
+
+    pub fn is_visited(&self, node: &N) -> bool {
+        self.visited.contains_key(node)
+    }
+
+    /// Moves on to the nodes one step beyond the current layer, returning
+    /// false once there are none left to visit.
+    pub fn advance(&mut self) -> bool {
+        let mut next = vec![];
+        for node in &self.layer {
+            for n in (self.neighbors)(node) {
+                if !self.visited.contains_key(&n) {
+                    self.visited.insert(n.clone(), (Some(node.clone()), self.depth + 1));
+                    next.push(n);
+                }
+            }
+        }
+        self.layer = next;
+        self.depth += 1;
+        !self.layer.is_empty()
+    }
+
+    /// The shortest path found to `end`, if the search has reached it.
+    pub fn path_to(&self, end: &N) -> Option<Path<N>> {
+        let nodes = walk_back(&self.visited, end)?;
+        let cost = self.visited[end].1 as u64;
+        Some(Path { nodes, cost })
+    }
+
+    /// Searches on until a node satisfying `goal` is reached.
+    pub fn find(&mut self, mut goal: impl FnMut(&N) -> bool) -> Option<Path<N>> {
+        loop {
+            if let Some(end) = self.layer.iter().find(|n| goal(n)) {
+                return self.path_to(end);
+            }
+            if !self.advance() {
+                return None;
+            }
+        }
+    }
+
+    /// Searches the whole reachable graph, returning each node's distance
+    /// from the nearest start.
+    pub fn distances(mut self) -> HashMap<N, usize> {
+        while self.advance() {}
+        self.visited.into_iter().map(|(n, (_, depth))| (n, depth)).collect()
+    }
+}
+
+/// The cheapest path from any of `starts` to a node satisfying `goal`, where
+/// `neighbors` gives each node's successors with the cost of the edge.
+pub fn dijkstra<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    neighbors: impl FnMut(&N) -> I,
+    goal: impl FnMut(&N) -> bool,
+) -> Option<Path<N>>
+where
+    N: Eq + Hash + Clone,
+    I: IntoIterator<Item = (N, u64)>,
+{
+    astar(starts, neighbors, |_| 0, goal)
+}
+
+/// As [`dijkstra`], exploring first where `heuristic` says the goal is
+/// nearest. The path is cheapest so long as the heuristic never
+/// overestimates the remaining cost.
+pub fn astar<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbors: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> u64,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Option<Path<N>>
+where
+    N: Eq + Hash + Clone,
+    I: IntoIterator<Item = (N, u64)>,
+{
+    // Nodes live in `nodes` so the heap can order them without `N: Ord`.
+    // Of equally promising entries the one furthest along comes out first,
+    // which keeps A* heading for the goal rather than widening out.
+    let mut nodes = vec![];
+    let mut heap = BinaryHeap::new();
+    let mut best: HashMap<N, (Option<N>, u64)> = HashMap::new();
+    for start in starts {
+        if best.insert(start.clone(), (None, 0)).is_none() {
+            heap.push((Reverse(heuristic(&start)), 0, Reverse(nodes.len())));
+            nodes.push(start);
+        }
+    }
+    while let Some((_, cost, Reverse(idx))) = heap.pop() {
+        let node = nodes[idx].clone();
+        if cost > best[&node].1 {
+            // already reached more cheaply
+            continue;
+        }
+        if goal(&node) {
+            let nodes = walk_back(&best, &node)?;
+            return Some(Path { nodes, cost });
+        }
+        for (n, edge) in neighbors(&node) {
+            let next = cost + edge;
+            if best.get(&n).is_none_or(|&(_, c)| next < c) {
+                best.insert(n.clone(), (Some(node.clone()), next));
+                heap.push((Reverse(next + heuristic(&n)), next, Reverse(nodes.len())));
+                nodes.push(n);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small weighted graph: 0 -> 1 -> 3 is short but dear, 0 -> 2 -> 4 -> 3
+    /// is longer but cheaper, and 5 can't be reached.
+    fn edges(n: &u32) -> Vec<(u32, u64)> {
+        match n {
+            0 => vec![(1, 1), (2, 1)],
+            1 => vec![(3, 10)],
+            2 => vec![(4, 1)],
+            4 => vec![(3, 1)],
+            _ => vec![],
+        }
+    }
+
+    fn unweighted(n: &u32) -> Vec<u32> {
+        edges(n).into_iter().map(|(n, _)| n).collect()
+    }
+
+    #[test]
+    fn test_bfs() {
+        let mut bfs = Bfs::new([0], unweighted);
+        let path = bfs.find(|&n| n == 3).unwrap();
+        assert_eq!(path, Path { nodes: vec![0, 1, 3], cost: 2 });
+        assert_eq!(path.steps(), 2);
+        assert!(bfs.is_visited(&4) && !bfs.is_visited(&5));
+        assert_eq!(bfs.find(|&n| n == 5), None);
+
+        let distances = Bfs::new([0], unweighted).distances();
+        assert_eq!(distances.len(), 5);
+        assert_eq!((distances[&0], distances[&4], distances[&3]), (0, 2, 2));
+    }
+
+    #[test]
+    fn test_bfs_layers() {
+        let mut bfs = Bfs::new([1, 2, 1], unweighted);
+        assert_eq!(bfs.layer(), &[1, 2]);
+        assert!(bfs.advance());
+        assert_eq!((bfs.depth(), bfs.layer()), (1, &[3, 4][..]));
+        assert!(!bfs.advance());
+        assert_eq!(bfs.path_to(&4).unwrap().nodes, vec![2, 4]);
+        assert_eq!(bfs.path_to(&0), None);
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let path = dijkstra([0], edges, |&n| n == 3).unwrap();
+        assert_eq!(path, Path { nodes: vec![0, 2, 4, 3], cost: 3 });
+        assert_eq!(dijkstra([1, 2], edges, |&n| n == 3).unwrap().nodes, vec![2, 4, 3]);
+        assert_eq!(dijkstra([0], edges, |&n| n == 5), None);
+    }
+
+    #[test]
+    fn test_astar() {
+        // walking a 10x10 open grid from corner to corner
+        let neighbors = |&(x, y): &(i32, i32)| {
+            [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                .into_iter()
+                .filter(|&(x, y)| (0..10).contains(&x) && (0..10).contains(&y))
+                .map(|p| (p, 1))
+        };
+        let mut expanded = 0;
+        let heuristic = |&(x, y): &(i32, i32)| {
+            expanded += 1;
+            (9 - x + 9 - y) as u64
+        };
+        let path = astar([(0, 0)], neighbors, heuristic, |&p| p == (9, 9)).unwrap();
+        assert_eq!((path.cost, path.steps()), (18, 18));
+        assert!(path.nodes.windows(2).all(|w| (w[0].0 - w[1].0).abs() + (w[0].1 - w[1].1).abs() == 1));
+        assert!(expanded < 100, "A* looked at {} squares", expanded);
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::answers::Answers;
+use crate::cancel::Timeout;
+use crate::cli::Options;
+use crate::input::InputSet;
+use crate::interface::*;
+use crate::runner::execute;
+
+/// The outcome of checking one part against the answer registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Fail { expected: String, actual: String },
+    Missing { actual: String },
+    Timeout,
+    Error(String),
+}
+
+impl Verdict {
+    pub fn check(expected: Option<&str>, actual: Result<Answer>) -> Verdict {
+        match (expected, actual) {
+            (_, Err(e)) if e.is::<Timeout>() => Verdict::Timeout,
+            (_, Err(e)) => Verdict::Error(format!("{:#}", e)),
+            (None, Ok(answer)) => Verdict::Missing {
+                actual: answer.to_string(),
+            },
+            (Some(expected), Ok(answer)) if answer.to_string() == expected => Verdict::Pass,
+            (Some(expected), Ok(answer)) => Verdict::Fail {
+                expected: expected.to_string(),
+                actual: answer.to_string(),
+            },
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Verdict::Fail { .. } | Verdict::Timeout | Verdict::Error(_))
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verdict::Pass => write!(f, "PASS"),
+            Verdict::Fail { expected, actual } => {
+                write!(f, "FAIL (expected {:?}, got {:?})", expected, actual)
+            }
+            Verdict::Missing { actual } => write!(f, "MISSING (got {:?})", actual),
+            Verdict::Timeout => write!(f, "TIMEOUT"),
+            Verdict::Error(e) => write!(f, "ERROR ({})", e),
+        }
+    }
+}
+
+/// Re-runs every selected part and compares it with `answers`, printing a
+/// verdict per part. With `opts.record`, answers for parts that have no
+/// entry yet are added to the registry. Returns the number of failures.
+pub fn run(
+    days: &[Arc<dyn Day>],
+    inputs: &InputSet,
+    answers: &mut Answers,
+    opts: &Options,
+) -> usize {
+    let mut failures = 0;
+    execute(days, inputs, opts, |result| {
+        for part in result.parts {
+            let verdict = Verdict::check(answers.get(result.day, part.part), part.answer);
+            println!("Day {:02} part {}: {}", result.day, part.part, verdict);
+            if verdict.is_failure() {
+                failures += 1;
+            }
+            if let (true, Verdict::Missing { actual }) = (opts.record, &verdict) {
+                answers.insert(result.day, part.part, &Answer::Text(actual.clone()));
+            }
+        }
+    });
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_check() {
+        assert_eq!(Verdict::check(Some("13"), Ok(13.into())), Verdict::Pass);
+        let fail = Verdict::check(Some("2713310158"), Ok(10197.into()));
+        assert!(fail.is_failure());
+        assert_eq!(
+            fail.to_string(),
+            "FAIL (expected \"2713310158\", got \"10197\")"
+        );
+        let missing = Verdict::check(None, Ok("CMZ".into()));
+        assert!(!missing.is_failure());
+        assert_eq!(missing.to_string(), "MISSING (got \"CMZ\")");
+        assert!(Verdict::check(Some("1"), Err(anyhow!("boom"))).is_failure());
+        let timeout = Verdict::check(Some("1"), Err(Timeout(Duration::from_secs(1)).into()));
+        assert_eq!(timeout, Verdict::Timeout);
+        assert!(timeout.is_failure());
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::cli::Options;
+use crate::fixtures;
+use crate::input::InputSet;
+use crate::interface::*;
+use crate::runner::run_day;
+
+/// How often `--watch` looks at the watched files.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A file's modification time and length, or `None` if it can't be read.
+/// The length catches edits made within the same mtime tick.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// A day and the files that make it re-run when they change.
+struct Watched {
+    day: Arc<dyn Day>,
+    fixture: PathBuf,
+    paths: Vec<PathBuf>,
+    stamps: Vec<Stamp>,
+}
+
+impl Watched {
+    fn changed(&mut self) -> bool {
+        let stamps: Vec<Stamp> = self.paths.iter().map(|p| stamp(p)).collect();
+        if stamps == self.stamps {
+            return false;
+        }
+        self.stamps = stamps;
+        true
+    }
+}
+
+/// Re-runs days when their input or fixture files change, comparing each
+/// answer with the one from the previous run.
+pub struct Watch<'a> {
+    days: Vec<Watched>,
+    inputs: &'a InputSet,
+    opts: &'a Options,
+    previous: BTreeMap<(u8, u8), String>,
+}
+
+fn describe(answer: &Result<Answer>) -> String {
+    match answer {
+        Ok(answer) => answer.to_string(),
+        Err(e) => format!("error: {:#}", e),
+    }
+}
+
+impl<'a> Watch<'a> {
+    /// Watches each day's input in `inputs` and its fixture under the crate
+    /// root `root`.
+    pub fn new(days: &[Arc<dyn Day>], inputs: &'a InputSet, opts: &'a Options, root: &Path) -> Self {
+        let days = days
+            .iter()
+            .map(|day| {
+                let fixture = fixtures::path(root, day.number());
+                let mut paths = vec![fixture.clone()];
+                paths.extend(inputs.path(day.number()));
+                let stamps = paths.iter().map(|p| stamp(p)).collect();
+                Watched {
+                    day: Arc::clone(day),
+                    fixture,
+                    paths,
+                    stamps,
+                }
+            })
+            .collect();
+        Watch {
+            days,
+            inputs,
+            opts,
+            previous: BTreeMap::new(),
+        }
+    }
+
+    /// Runs every day once, returning the lines to print.
+    pub fn start(&mut self) -> Vec<String> {
+        (0..self.days.len()).flat_map(|i| self.run(i)).collect()
+    }
+
+    /// Re-runs the days whose files changed since the last call.
+    pub fn poll(&mut self) -> Vec<String> {
+        let changed: Vec<usize> = (0..self.days.len()).filter(|&i| self.days[i].changed()).collect();
+        changed.into_iter().flat_map(|i| self.run(i)).collect()
+    }
+
+    fn run(&mut self, idx: usize) -> Vec<String> {
+        let day = Arc::clone(&self.days[idx].day);
+        let n = day.number();
+        let result = run_day(&day, self.inputs, self.opts);
+        let mut lines = vec![];
+        for part in &result.parts {
+            let current = describe(&part.answer);
+            let note = match self.previous.get(&(n, part.part)) {
+                None => String::new(),
+                Some(was) if *was == current => " (unchanged)".to_string(),
+                Some(was) if was.contains('\n') || current.contains('\n') => " (changed)".to_string(),
+                Some(was) => format!(" (was {})", was),
+            };
+            lines.push(if current.contains('\n') {
+                format!("Day {:02} part {} in {:.1?}{}:\n{}", n, part.part, part.elapsed, note, current)
+            } else {
+                format!("Day {:02} part {}: {} in {:.1?}{}", n, part.part, current, part.elapsed, note)
+            });
+            self.previous.insert((n, part.part), current);
+        }
+        lines.extend(self.check_examples(day.as_ref(), &self.days[idx].fixture));
+        lines
+    }
+
+    fn check_examples(&self, day: &dyn Day, fixture: &Path) -> Option<String> {
+        if !fixture.exists() {
+            return None;
+        }
+        let examples = match fixtures::read(fixture) {
+            Ok(examples) => examples,
+            Err(e) => return Some(format!("Day {:02} examples: {:#}", day.number(), e)),
+        };
+        let mut passed = 0;
+        let mut failed = vec![];
+        for (i, example) in examples.iter().enumerate() {
+            match fixtures::check(day, example) {
+                Ok(verdicts) => {
+                    for (part, verdict) in verdicts {
+                        if verdict.is_failure() {
+                            failed.push(format!("example {} part {}: {}", i + 1, part, verdict));
+                        } else {
+                            passed += 1;
+                        }
+                    }
+                }
+                Err(e) => failed.push(format!("example {}: {:#}", i + 1, e)),
+            }
+        }
+        let mut line = format!("Day {:02} examples: {} passed, {} failed", day.number(), passed, failed.len());
+        for f in failed {
+            line += &format!("\n  {}", f);
+        }
+        Some(line)
+    }
+}
+
+/// Runs the days, then re-runs them whenever their files change. Only
+/// returns if the process is interrupted.
+pub fn run(days: &[Arc<dyn Day>], inputs: &InputSet, opts: &Options) {
+    let mut watch = Watch::new(days, inputs, opts, Path::new(env!("CARGO_MANIFEST_DIR")));
+    for line in watch.start() {
+        println!("{}", line);
+    }
+    println!("Watching for changes, press Ctrl-C to stop");
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        for line in watch.poll() {
+            println!("{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::days::day06;
+
+    #[test]
+    fn test_watch() {
+        let dir = std::env::temp_dir().join(format!("advent-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input06.txt");
+        fs::write(&input, "mjqjpqmgbljsphdztnvjfqwrcgsmlb\n").unwrap();
+
+        let days: Vec<Arc<dyn Day>> = vec![Arc::new(day06::D {})];
+        let inputs = InputSet::dir(&dir);
+        let opts = Options::default();
+        let mut watch = Watch::new(&days, &inputs, &opts, &dir);
+        let lines = watch.start();
+        // no fixture yet
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("Day 06 part 1: 7 in "), "{}", lines[0]);
+        assert!(!lines[0].contains('('));
+        assert!(watch.poll().is_empty());
+
+        fs::write(&input, "bvwbjplbgvbhsrlpgdmjqwftvncz\n").unwrap();
+        let lines = watch.poll();
+        assert!(lines[0].starts_with("Day 06 part 1: 5 in "), "{}", lines[0]);
+        assert!(lines[0].ends_with(" (was 7)"), "{}", lines[0]);
+        assert!(lines[1].ends_with(" (was 19)"), "{}", lines[1]);
+
+        fs::create_dir_all(dir.join(fixtures::DIR)).unwrap();
+        fs::write(
+            fixtures::path(&dir, 6),
+            "--- input\nmjqjpqmgbljsphdztnvjfqwrcgsmlb\n--- part1 7\n--- part2 20\n",
+        )
+        .unwrap();
+        let lines = watch.poll();
+        assert!(lines[0].ends_with(" (unchanged)"), "{}", lines[0]);
+        assert_eq!(
+            lines[2],
+            "Day 06 examples: 1 passed, 1 failed\n  example 1 part 2: FAIL (expected \"20\", got \"19\")"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}